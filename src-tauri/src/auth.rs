@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock as StdRwLock;
+
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use tokio::sync::Mutex;
+
+use crate::models::{ApiError, Result, Shared};
+use crate::tasks::http_body::HttpBody;
+use crate::tasks::http_task::parse_method;
+use crate::utils::{get_handlebars, get_json_val};
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct AuthConfig {
+    pub method: AuthMethod,
+    /// env name the token is stored under, "TOKEN" when omitted
+    pub token_name: Option<String>,
+    /// headers added to every HttpJob request, templated over the env with the token;
+    /// `Authorization: Basic/Bearer {{TOKEN}}` when omitted, `{}` to use the token only in your own templates
+    pub header: Option<HashMap<String, String>>,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone)]
+pub enum AuthMethod {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+    OAuth2(OAuth2Auth),
+    Login(LoginAuth),
+}
+
+/// Client-credentials grant, or refresh-token grant when `refresh_token` is set or returned.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct OAuth2Auth {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scope: Option<String>,
+    pub refresh_token: Option<String>,
+    /// JSONPath to the token, "$.access_token" when omitted
+    pub token_path: Option<String>,
+    /// JSONPath to a new refresh token, "$.refresh_token" when omitted
+    pub refresh_token_path: Option<String>,
+}
+
+/// Custom login request; cookies it sets land in the cookie jar.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct LoginAuth {
+    pub url: String,
+    pub method: String,
+    #[serde(default)]
+    pub header: HashMap<String, String>,
+    pub body: Option<HttpBody>,
+    /// JSONPath to the token; without it the login only sets cookies
+    pub token_path: Option<String>,
+}
+
+#[derive(Clone, Default)]
+struct AuthToken {
+    access: String,
+    refresh: Option<String>,
+}
+
+/// Logs in once, shares the token with every HttpJob and logs in again on 401.
+pub struct AuthSession {
+    pub config: AuthConfig,
+    pub client: Client,
+    /// `Scraping.env`; credentials are rendered from it and the token is written back to it
+    pub env: Shared<HashMap<String, String>>,
+    token: StdRwLock<Option<AuthToken>>,
+    login_lock: Mutex<()>,
+}
+
+impl AuthSession {
+    pub fn new(config: AuthConfig, client: Client, env: Shared<HashMap<String, String>>) -> Self {
+        AuthSession {
+            config,
+            client,
+            env,
+            token: StdRwLock::new(None),
+            login_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn token_name(&self) -> String {
+        self.config.token_name.clone().unwrap_or("TOKEN".to_string())
+    }
+
+    /// Token from the last login, without logging in.
+    pub fn current(&self) -> Option<String> {
+        self.token.read().unwrap().as_ref().map(|token| token.access.clone())
+    }
+
+    pub async fn token(&self) -> Result<String> {
+        if let Some(token) = self.current() {
+            return Ok(token);
+        }
+        let _guard = self.login_lock.lock().await;
+        if let Some(token) = self.current() {
+            return Ok(token);
+        }
+        self.login(None).await
+    }
+
+    /// Called after a 401 with the token that was rejected; concurrent callers share one login.
+    pub async fn refresh(&self, stale: &str) -> Result<String> {
+        let _guard = self.login_lock.lock().await;
+        let current = self.token.read().unwrap().clone();
+        match current {
+            Some(token) if token.access != stale => Ok(token.access),
+            Some(token) => self.login(token.refresh).await,
+            None => self.login(None).await,
+        }
+    }
+
+    pub async fn header(&self, token: &str) -> Result<HeaderMap> {
+        let mut env = self.env.read().await.clone();
+        env.insert(self.token_name(), token.to_string());
+        let mut header = HeaderMap::new();
+        for (k, v) in self.header_templates().iter() {
+            let nm = HeaderName::from_str(k.as_str())?;
+            let val = HeaderValue::from_str(&get_handlebars(v, &env)?)?;
+            header.insert(nm, val);
+        }
+        Ok(header)
+    }
+
+    fn header_templates(&self) -> HashMap<String, String> {
+        if let Some(header) = &self.config.header {
+            return header.clone();
+        }
+        let token = format!("{{{{{}}}}}", self.token_name());
+        let value = match &self.config.method {
+            AuthMethod::Basic { .. } => format!("Basic {}", token),
+            AuthMethod::Login(login) if login.token_path.is_none() => return HashMap::new(),
+            _ => format!("Bearer {}", token),
+        };
+        HashMap::from([("Authorization".to_string(), value)])
+    }
+
+    async fn login(&self, refresh: Option<String>) -> Result<String> {
+        let env = self.env.read().await.clone();
+        let token = match &self.config.method {
+            AuthMethod::Basic { username, password } => {
+                let credentials = format!("{}:{}", get_handlebars(username, &env)?, get_handlebars(password, &env)?);
+                AuthToken {
+                    access: base64::engine::general_purpose::STANDARD.encode(credentials),
+                    refresh: None,
+                }
+            }
+            AuthMethod::Bearer { token } => AuthToken {
+                access: get_handlebars(token, &env)?,
+                refresh: None,
+            },
+            AuthMethod::OAuth2(oauth2) => {
+                let refresh = refresh.or(oauth2.refresh_token.as_ref().map(|t| get_handlebars(t, &env)).transpose()?);
+                match refresh {
+                    Some(refresh) => match self.oauth2(oauth2, &env, Some(refresh)).await {
+                        Ok(token) => token,
+                        Err(e) if oauth2.client_secret.is_some() => {
+                            eprintln!("Error: {:?}", e);
+                            self.oauth2(oauth2, &env, None).await?
+                        }
+                        Err(e) => return Err(e),
+                    },
+                    None => self.oauth2(oauth2, &env, None).await?,
+                }
+            }
+            AuthMethod::Login(login) => self.custom_login(login, &env).await?,
+        };
+
+        eprintln!("auth: logged in");
+        let access = token.access.clone();
+        self.env.write().await.insert(self.token_name(), access.clone());
+        *self.token.write().unwrap() = Some(token);
+        Ok(access)
+    }
+
+    async fn oauth2(&self, oauth2: &OAuth2Auth, env: &HashMap<String, String>, refresh: Option<String>) -> Result<AuthToken> {
+        let mut form = vec![("client_id", get_handlebars(&oauth2.client_id, env)?)];
+        if let Some(client_secret) = &oauth2.client_secret {
+            form.push(("client_secret", get_handlebars(client_secret, env)?));
+        }
+        if let Some(scope) = &oauth2.scope {
+            form.push(("scope", get_handlebars(scope, env)?));
+        }
+        match &refresh {
+            Some(refresh) => {
+                form.push(("grant_type", "refresh_token".to_string()));
+                form.push(("refresh_token", refresh.clone()));
+            }
+            None => form.push(("grant_type", "client_credentials".to_string())),
+        }
+
+        let url = get_handlebars(&oauth2.token_url, env)?;
+        let res = self.client.post(&url).form(&form).send().await?;
+        let json = response_json(res, &url).await?;
+        let token_path = oauth2.token_path.clone().unwrap_or("$.access_token".to_string());
+        let refresh_token_path = oauth2.refresh_token_path.clone().unwrap_or("$.refresh_token".to_string());
+        Ok(AuthToken {
+            access: select_token(&json, &token_path)?,
+            refresh: get_json_val(&json, &refresh_token_path).or(refresh),
+        })
+    }
+
+    async fn custom_login(&self, login: &LoginAuth, env: &HashMap<String, String>) -> Result<AuthToken> {
+        let url = get_handlebars(&login.url, env)?;
+        let method = parse_method(&login.method, false)?;
+        let mut header = HeaderMap::new();
+        for (k, v) in login.header.iter() {
+            let nm = HeaderName::from_str(k.as_str())?;
+            let val = HeaderValue::from_str(&get_handlebars(v, env)?)?;
+            header.insert(nm, val);
+        }
+
+        let mut req_builder = self.client.request(method, &url).headers(header);
+        if let Some(body) = &login.body {
+            req_builder = body.render(env)?.apply(req_builder).await?;
+        }
+        let res = req_builder.send().await?;
+        let access = match &login.token_path {
+            Some(token_path) => select_token(&response_json(res, &url).await?, token_path)?,
+            None => {
+                if !res.status().is_success() {
+                    return Err(ApiError::AuthError(format!("{} returned {}", url, res.status())));
+                }
+                String::new()
+            }
+        };
+        Ok(AuthToken { access, refresh: None })
+    }
+}
+
+// Debug output ends up in logs, so credentials, tokens and header values are never printed.
+const REDACTED: &str = "***";
+
+fn redact(val: &Option<String>) -> Option<&'static str> {
+    val.as_ref().map(|_| REDACTED)
+}
+
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("method", &self.method)
+            .field("token_name", &self.token_name)
+            .field("header", &self.header.as_ref().map(|header| header.keys().collect::<Vec<_>>()))
+            .finish()
+    }
+}
+
+impl fmt::Debug for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthMethod::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &REDACTED)
+                .finish(),
+            AuthMethod::Bearer { .. } => f.debug_struct("Bearer").field("token", &REDACTED).finish(),
+            AuthMethod::OAuth2(oauth2) => f.debug_tuple("OAuth2").field(oauth2).finish(),
+            AuthMethod::Login(login) => f.debug_tuple("Login").field(login).finish(),
+        }
+    }
+}
+
+impl fmt::Debug for OAuth2Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2Auth")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &redact(&self.client_secret))
+            .field("scope", &self.scope)
+            .field("refresh_token", &redact(&self.refresh_token))
+            .field("token_path", &self.token_path)
+            .field("refresh_token_path", &self.refresh_token_path)
+            .finish()
+    }
+}
+
+impl fmt::Debug for LoginAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginAuth")
+            .field("url", &self.url)
+            .field("method", &self.method)
+            .field("header", &self.header.keys().collect::<Vec<_>>())
+            .field("body", &self.body.as_ref().map(|_| REDACTED))
+            .field("token_path", &self.token_path)
+            .finish()
+    }
+}
+
+impl fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("access", &REDACTED)
+            .field("refresh", &redact(&self.refresh))
+            .finish()
+    }
+}
+
+impl fmt::Debug for AuthSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthSession")
+            .field("config", &self.config)
+            .field("token", &*self.token.read().unwrap())
+            .finish_non_exhaustive()
+    }
+}
+
+async fn response_json(res: reqwest::Response, url: &str) -> Result<Value> {
+    let status = res.status();
+    let text = res.text().await?;
+    if !status.is_success() {
+        return Err(ApiError::AuthError(format!("{} returned {}: {}", url, status, text)));
+    }
+    serde_json::from_str(&text).map_err(|e| ApiError::AuthError(format!("{}: {}", url, e)))
+}
+
+fn select_token(json: &Value, token_path: &str) -> Result<String> {
+    match get_json_val(json, token_path) {
+        Some(token) if !token.is_empty() && token != "null" => Ok(token),
+        _ => Err(ApiError::AuthError(format!("no token at {}", token_path))),
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(tr_scraping_lib::run_cli())
+}
@@ -0,0 +1,149 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::models::{ApiError, Result, Setting, StepEvent};
+use crate::scraping::Scraping;
+use crate::sinks::event_sink::{EventSink, SharedSink};
+use crate::sinks::json_lines_sink::JsonLinesSink;
+use crate::sinks::stdout_sink::StdoutSink;
+
+const USAGE: &str = "usage: tr-scraping-cli run <scraping.json> [--step <name>]... [--log <file.jsonl>]
+       tr-scraping-cli preview <scraping.json> --step <name> [--limit <n>]";
+const PREVIEW_LIMIT: usize = 20;
+
+enum CliCommand {
+    Run,
+    Preview,
+}
+
+struct CliSink {
+    inner: SharedSink,
+    failed: Arc<AtomicUsize>,
+}
+
+impl EventSink for CliSink {
+    fn emit(&self, event: StepEvent) {
+        if matches!(event, StepEvent::TaskFailed { .. } | StepEvent::StepFailed { .. }) {
+            self.failed.fetch_add(1, Ordering::SeqCst);
+        }
+        self.inner.emit(event);
+    }
+}
+
+struct CliArgs {
+    command: CliCommand,
+    setting_path: String,
+    steps: Vec<String>,
+    log_path: Option<String>,
+    limit: usize,
+}
+
+pub fn run_cli() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli_args = match parse_args(&args) {
+        Ok(cli_args) => cli_args,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            return 1;
+        }
+    };
+
+    let failed = Arc::new(AtomicUsize::new(0));
+    let res = runtime.block_on(run(cli_args, failed.clone()));
+    if let Err(e) = res {
+        eprintln!("Error: {}", e);
+        return 1;
+    }
+    let failed = failed.load(Ordering::SeqCst);
+    if failed > 0 {
+        eprintln!("failed: {}", failed);
+        return 1;
+    }
+    0
+}
+
+fn parse_args(args: &[String]) -> Result<CliArgs> {
+    let mut iter = args.iter();
+    let command = match iter.next().map(|s| s.as_str()) {
+        Some("run") => CliCommand::Run,
+        Some("preview") => CliCommand::Preview,
+        Some(cmd) => return Err(ApiError::ParseError(format!("unknown command: {}", cmd))),
+        None => return Err(ApiError::ParseError("missing command".to_string())),
+    };
+
+    let mut setting_path: Option<String> = None;
+    let mut steps = Vec::new();
+    let mut log_path: Option<String> = None;
+    let mut limit = PREVIEW_LIMIT;
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--step" => {
+                let Some(step) = iter.next() else {
+                    return Err(ApiError::ParseError("--step needs a value".to_string()));
+                };
+                steps.push(step.clone());
+            }
+            "--log" => {
+                let Some(path) = iter.next() else {
+                    return Err(ApiError::ParseError("--log needs a value".to_string()));
+                };
+                log_path = Some(path.clone());
+            }
+            "--limit" => {
+                let Some(n) = iter.next() else {
+                    return Err(ApiError::ParseError("--limit needs a value".to_string()));
+                };
+                limit = n.parse::<usize>()?;
+            }
+            _ if setting_path.is_none() => setting_path = Some(arg.clone()),
+            _ => return Err(ApiError::ParseError(format!("unexpected argument: {}", arg))),
+        }
+    }
+
+    let Some(setting_path) = setting_path else {
+        return Err(ApiError::ParseError("missing scraping.json".to_string()));
+    };
+    if matches!(command, CliCommand::Preview) && steps.len() != 1 {
+        return Err(ApiError::ParseError("preview needs one --step".to_string()));
+    }
+    Ok(CliArgs { command, setting_path, steps, log_path, limit })
+}
+
+async fn run(cli_args: CliArgs, failed: Arc<AtomicUsize>) -> Result<()> {
+    let json_str = tokio::fs::read_to_string(&cli_args.setting_path).await?;
+    let setting: Setting = serde_json::from_str(&json_str)?;
+
+    let mut scraping = Scraping::new();
+    scraping.load(setting).await?;
+
+    if let CliCommand::Preview = cli_args.command {
+        for step_name in cli_args.steps {
+            let preview = scraping.preview_step(step_name, cli_args.limit).await?;
+            println!("{}", serde_json::to_string_pretty(&preview)?);
+        }
+        return Ok(());
+    }
+
+    let inner: SharedSink = match cli_args.log_path {
+        Some(log_path) => Arc::new(JsonLinesSink::new(&log_path)?),
+        None => Arc::new(StdoutSink),
+    };
+    let sink: SharedSink = Arc::new(CliSink { inner, failed });
+    if cli_args.steps.is_empty() {
+        scraping.run_pipeline(sink).await?;
+    } else {
+        for step_name in cli_args.steps {
+            scraping.run_step(step_name, sink.clone()).await?;
+        }
+    }
+    Ok(())
+}
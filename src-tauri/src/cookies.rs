@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use cookie::time::OffsetDateTime;
+use cookie_store::{CookieDomain, CookieExpiration, CookieStore, RawCookie};
+use reqwest::header::HeaderValue;
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::models::{ApiError, Result};
+use crate::utils::get_handlebars;
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct CookieConfig {
+    /// Netscape cookies.txt, loaded on setting load and rewritten after each step.
+    pub file: Option<String>,
+    /// Url to `name=value; name2=value2` cookies set before the first request.
+    pub seed: Option<HashMap<String, String>>,
+}
+
+/// Cookie store shared by every request made through `Scraping.client`.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    pub store: RwLock<CookieStore>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+
+    /// Clears the jar, then loads the cookie file (if it exists) and the seed cookies.
+    /// Returns the resolved cookie file path.
+    pub fn load(&self, config: &Option<CookieConfig>, env: &HashMap<String, String>) -> Result<Option<String>> {
+        let mut store = self.store.write().unwrap();
+        store.clear();
+        let Some(config) = config else { return Ok(None); };
+
+        let file = match &config.file {
+            Some(file) => Some(get_handlebars(file, env)?),
+            None => None,
+        };
+        if let Some(file) = &file {
+            if Path::new(file).exists() {
+                let text = std::fs::read_to_string(file)?;
+                load_netscape(&mut store, &text)?;
+            }
+        }
+
+        for (url, cookies) in config.seed.iter().flatten() {
+            let url = get_handlebars(url, env)?;
+            let url = Url::parse(&url).map_err(|e| ApiError::ParseError(format!("{}: {}", url, e)))?;
+            for cookie in get_handlebars(cookies, env)?.split(';') {
+                let cookie = cookie.trim();
+                if cookie.is_empty() {
+                    continue;
+                }
+                store
+                    .parse(cookie, &url)
+                    .map_err(|e| ApiError::ParseError(format!("{}: {}", cookie, e)))?;
+            }
+        }
+        Ok(file)
+    }
+
+    /// An explicit `Cookie` header with the jar's cookies for `url` merged in. The jar wins on a name
+    /// both have, since it holds what the server sent last; reqwest alone would drop the jar entirely.
+    pub fn merge_cookie_header(&self, explicit: &str, url: &Url) -> String {
+        let store = self.store.read().unwrap();
+        let mut pairs: Vec<(String, String)> = explicit
+            .split(';')
+            .map(|pair| pair.trim())
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+                None => (String::new(), pair.to_string()),
+            })
+            .collect();
+        for (name, value) in store.get_request_values(url) {
+            match pairs.iter_mut().find(|(n, _)| n == name) {
+                Some(pair) => pair.1 = value.to_string(),
+                None => pairs.push((name.to_string(), value.to_string())),
+            }
+        }
+        pairs
+            .into_iter()
+            .map(|(name, value)| if name.is_empty() { value } else { format!("{}={}", name, value) })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    pub fn save(&self, file: &str) -> Result<()> {
+        let text = save_netscape(&self.store.read().unwrap());
+        if let Some(parent) = Path::new(file).parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        std::fs::write(file, text)?;
+        Ok(())
+    }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies = cookie_headers
+            .filter_map(|val| val.to_str().ok())
+            .filter_map(|val| RawCookie::parse(val.to_string()).ok())
+            .collect::<Vec<_>>();
+        self.store.write().unwrap().store_response_cookies(cookies.into_iter(), url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .store
+            .read()
+            .unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
+
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// `domain \t include_subdomains \t path \t secure \t expires \t name \t value`
+fn load_netscape(store: &mut CookieStore, text: &str) -> Result<()> {
+    for (i, line) in text.lines().enumerate() {
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [domain, subdomains, path, secure, expires, name, value] = fields[..] else {
+            return Err(ApiError::ParseError(format!("cookies.txt line {}: expected 7 fields", i + 1)));
+        };
+        let expires: i64 = expires.trim().parse()?;
+        let secure = secure.eq_ignore_ascii_case("TRUE");
+        let host = domain.trim_start_matches('.');
+
+        let mut cookie = RawCookie::build((name.to_string(), value.to_string()))
+            .path(path.to_string())
+            .secure(secure)
+            .http_only(http_only);
+        if subdomains.eq_ignore_ascii_case("TRUE") {
+            cookie = cookie.domain(host.to_string());
+        }
+        if expires > 0 {
+            let expires = OffsetDateTime::from_unix_timestamp(expires)
+                .map_err(|e| ApiError::ParseError(format!("cookies.txt line {}: {}", i + 1, e)))?;
+            cookie = cookie.expires(expires);
+        }
+
+        let scheme = if secure { "https" } else { "http" };
+        let url = Url::parse(&format!("{}://{}{}", scheme, host, path))
+            .map_err(|e| ApiError::ParseError(format!("cookies.txt line {}: {}", i + 1, e)))?;
+        // expired cookies are rejected here, which is fine
+        let _ = store.insert_raw(&cookie.build(), &url);
+    }
+    Ok(())
+}
+
+fn save_netscape(store: &CookieStore) -> String {
+    let mut text = String::from("# Netscape HTTP Cookie File\n");
+    for cookie in store.iter_unexpired() {
+        let (domain, subdomains) = match &cookie.domain {
+            CookieDomain::HostOnly(host) => (host.clone(), "FALSE"),
+            CookieDomain::Suffix(suffix) => (format!(".{}", suffix), "TRUE"),
+            _ => continue,
+        };
+        let expires = match &cookie.expires {
+            CookieExpiration::AtUtc(at) => at.unix_timestamp(),
+            CookieExpiration::SessionEnd => 0,
+        };
+        let prefix = if cookie.http_only().unwrap_or(false) { HTTP_ONLY_PREFIX } else { "" };
+        let secure = if cookie.secure().unwrap_or(false) { "TRUE" } else { "FALSE" };
+        text.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            prefix, domain, subdomains, &*cookie.path, secure, expires, cookie.name(), cookie.value()
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOKIES_TXT: &str = "# Netscape HTTP Cookie File\n\
+        .example.com\tTRUE\t/\tFALSE\t4102444800\tpref\tdark\n\
+        #HttpOnly_api.example.com\tFALSE\t/v1\tTRUE\t0\tsid\tabc123\n";
+
+    fn sorted_lines(text: &str) -> Vec<&str> {
+        let mut lines: Vec<&str> = text.lines().filter(|line| !line.starts_with("# ")).collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn netscape_round_trip() {
+        let mut store = CookieStore::default();
+        load_netscape(&mut store, COOKIES_TXT).unwrap();
+
+        let url = Url::parse("https://api.example.com/v1/items").unwrap();
+        let mut sent: Vec<_> = store.get_request_values(&url).collect();
+        sent.sort();
+        assert_eq!(sent, [("pref", "dark"), ("sid", "abc123")]);
+        // secure cookie, path outside /v1
+        assert_eq!(store.get_request_values(&Url::parse("http://api.example.com/").unwrap()).count(), 1);
+
+        let saved = save_netscape(&store);
+        assert_eq!(sorted_lines(&saved), sorted_lines(COOKIES_TXT));
+
+        let mut reloaded = CookieStore::default();
+        load_netscape(&mut reloaded, &saved).unwrap();
+        assert_eq!(sorted_lines(&save_netscape(&reloaded)), sorted_lines(&saved));
+    }
+
+    #[test]
+    fn netscape_rejects_short_lines() {
+        let mut store = CookieStore::default();
+        let err = load_netscape(&mut store, "example.com\tFALSE\t/\n");
+        assert!(matches!(err, Err(ApiError::ParseError(e)) if e.contains("line 1")));
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::path::Path;
+use async_stream::stream;
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::models::{ApiError, IterCsv};
+use crate::iters::glob_json::read_text;
+use crate::utils::get_handlebars;
+
+impl ItemIter for IterCsv {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_csv = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let path = match get_handlebars(&iter_csv.path, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
+            };
+            let delimiter = match iter_csv.delimiter.as_deref() {
+                None | Some("") => b',',
+                Some("\\t") => b'\t',
+                Some(d) if d.len() == 1 => d.as_bytes()[0],
+                Some(d) => {
+                    yield Err(ApiError::IterError(format!("{}: delimiter must be one byte, got {:?}", path, d)));
+                    return;
+                }
+            };
+            let has_header = iter_csv.has_header.unwrap_or(true);
+
+            let text = match read_text(Path::new(&path), iter_csv.encoding.as_deref()) {
+                Ok(text) => text,
+                Err(e) => { yield Err(e); return; }
+            };
+
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(delimiter)
+                .has_headers(has_header)
+                .flexible(true)
+                .from_reader(text.as_bytes());
+
+            let columns: Vec<String> = match (&iter_csv.columns, has_header) {
+                (Some(columns), _) => columns.clone(),
+                (None, true) => match reader.headers() {
+                    Ok(headers) => headers.iter().map(|h| h.trim().to_string()).collect(),
+                    Err(e) => { yield Err(ApiError::IterError(format!("{}: {}", path, e))); return; }
+                },
+                (None, false) => {
+                    yield Err(ApiError::IterError(format!("{}: columns are required without a header row", path)));
+                    return;
+                }
+            };
+
+            for (idx, record) in reader.records().enumerate() {
+                let record = match record {
+                    Ok(record) => record,
+                    Err(e) => { yield Err(ApiError::IterError(format!("{} row {}: {}", path, idx + 1, e))); continue; }
+                };
+                let row: HashMap<String, String> = columns
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(k, v)| (k.clone(), v.trim().to_string()))
+                    .collect();
+                match &iter_csv.env_map {
+                    Some(env_map) => {
+                        let mut env_item = HashMap::new();
+                        for (k, col) in env_map.iter() {
+                            if let Some(v) = row.get(col) {
+                                env_item.insert(k.to_string(), v.clone());
+                            }
+                        }
+                        yield Ok(env_item);
+                    }
+                    None => yield Ok(row),
+                }
+            }
+        })
+    }
+}
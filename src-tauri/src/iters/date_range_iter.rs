@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use async_stream::stream;
+use chrono::{Local, Months, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::models::{ApiError, DateUnit, IterDateRange, Result};
+use crate::utils::get_handlebars;
+
+impl ItemIter for IterDateRange {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_date_range = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let name = iter_date_range.name.clone();
+            let tz = match iter_date_range.timezone.as_deref() {
+                None => None,
+                Some(timezone) => match timezone.parse::<Tz>() {
+                    Ok(tz) => Some(tz),
+                    Err(e) => { yield Err(ApiError::IterError(format!("{}: {}", name, e))); return; }
+                },
+            };
+            let start = match parse_date(&iter_date_range.start, &env, tz) {
+                Ok(v) => v,
+                Err(e) => { yield Err(ApiError::IterError(format!("{}: start {:?}", name, e))); return; }
+            };
+            let end = match parse_date(&iter_date_range.end, &env, tz) {
+                Ok(v) => v,
+                Err(e) => { yield Err(ApiError::IterError(format!("{}: end {:?}", name, e))); return; }
+            };
+            let step = iter_date_range.step.unwrap_or(1).max(1);
+            let unit = iter_date_range.unit.clone().unwrap_or_default();
+            let format = iter_date_range.format.clone().unwrap_or("%Y-%m-%d".to_string());
+            let inclusive = iter_date_range.inclusive.unwrap_or(false);
+
+            // offsets are taken from `start` so month steps don't drift after a short month
+            for i in 0.. {
+                let Some(cur) = add_steps(start, &unit, step * i) else { break; };
+                if cur > end || (!inclusive && cur == end) {
+                    break;
+                }
+                match format_date(cur, tz, &format) {
+                    Ok(Some(val)) => yield Ok(HashMap::from([(name.clone(), val)])),
+                    // local time skipped by a DST change
+                    Ok(None) => continue,
+                    Err(e) => { yield Err(ApiError::IterError(format!("{}: {:?}", name, e))); return; }
+                }
+            }
+        })
+    }
+}
+
+fn parse_date(pattern: &str, env: &HashMap<String, String>, tz: Option<Tz>) -> Result<NaiveDateTime> {
+    let s = get_handlebars(pattern, env)?;
+    let s = s.trim();
+    let now = match tz {
+        Some(tz) => Utc::now().with_timezone(&tz).naive_local(),
+        None => Local::now().naive_local(),
+    };
+    match s {
+        "now" => return Ok(now),
+        "today" => return Ok(now.date().and_hms_opt(0, 0, 0).unwrap_or(now)),
+        _ => {}
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(v) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Ok(v);
+        }
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+fn add_steps(start: NaiveDateTime, unit: &DateUnit, n: u32) -> Option<NaiveDateTime> {
+    match unit {
+        DateUnit::Hours => start.checked_add_signed(TimeDelta::try_hours(n as i64)?),
+        DateUnit::Days => start.checked_add_signed(TimeDelta::try_days(n as i64)?),
+        DateUnit::Months => start.checked_add_months(Months::new(n)),
+    }
+}
+
+fn format_date(cur: NaiveDateTime, tz: Option<Tz>, format: &str) -> Result<Option<String>> {
+    let mut formatted = String::new();
+    let res = match tz {
+        Some(tz) => match tz.from_local_datetime(&cur).earliest() {
+            Some(d) => write!(formatted, "{}", d.format(format)),
+            None => return Ok(None),
+        },
+        None => match Local.from_local_datetime(&cur).earliest() {
+            Some(d) => write!(formatted, "{}", d.format(format)),
+            None => return Ok(None),
+        },
+    };
+    res.map_err(|_| ApiError::ParseError(format!("invalid format {:?}", format)))?;
+    Ok(Some(formatted))
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::path::Path;
+use async_stream::stream;
+use chrono::{DateTime, Local};
+use glob::{MatchOptions, Pattern};
+use mime_guess::from_path;
+use walkdir::WalkDir;
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::models::{ApiError, IterFiles, ItemData, Result};
+use crate::utils::get_handlebars;
+
+impl ItemIter for IterFiles {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_files = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let name = iter_files.name.clone();
+            let dir = match get_handlebars(&iter_files.dir, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
+            };
+            let include = match parse_patterns(&iter_files.include) {
+                Ok(v) => v,
+                Err(e) => { yield Err(ApiError::IterError(format!("{}: include {:?}", name, e))); return; }
+            };
+            let exclude = match parse_patterns(&iter_files.exclude) {
+                Ok(v) => v,
+                Err(e) => { yield Err(ApiError::IterError(format!("{}: exclude {:?}", name, e))); return; }
+            };
+            let max_depth = match (iter_files.recursive.unwrap_or(false), iter_files.max_depth) {
+                (false, _) => 1,
+                (true, Some(max_depth)) => max_depth,
+                (true, None) => usize::MAX,
+            };
+
+            let walker = WalkDir::new(&dir).max_depth(max_depth).sort_by_file_name();
+            for entry in walker {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => { yield Err(ApiError::IterError(format!("{}: {}", dir, e))); continue; }
+                };
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let rel = entry.path().strip_prefix(&dir).unwrap_or(entry.path());
+                if !include.is_empty() && !is_match(&include, rel) {
+                    continue;
+                }
+                if is_match(&exclude, rel) {
+                    continue;
+                }
+                match file_item(&name, entry.path()) {
+                    Ok(item) => yield Ok(item),
+                    Err(e) => yield Err(ApiError::IterError(format!("{}: {:?}", entry.path().display(), e))),
+                }
+            }
+        })
+    }
+}
+
+fn parse_patterns(patterns: &Option<Vec<String>>) -> Result<Vec<Pattern>> {
+    let mut parsed = Vec::new();
+    for pattern in patterns.iter().flatten() {
+        parsed.push(Pattern::new(pattern)?);
+    }
+    Ok(parsed)
+}
+
+/// A pattern without `/` is matched against the file name alone.
+fn is_match(patterns: &[Pattern], rel: &Path) -> bool {
+    let options = MatchOptions { require_literal_separator: true, ..MatchOptions::new() };
+    let file_name = rel.file_name().map(Path::new).unwrap_or(rel);
+    patterns.iter().any(|pattern| {
+        let target = if pattern.as_str().contains('/') { rel } else { file_name };
+        pattern.matches_path_with(target, options)
+    })
+}
+
+fn file_item(name: &str, path: &Path) -> Result<ItemData> {
+    let metadata = path.metadata()?;
+    let mtime = metadata
+        .modified()
+        .map(|t| DateTime::<Local>::from(t).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    let mime_type = match infer::get_from_path(path) {
+        Ok(Some(infer_type)) => infer_type.mime_type().to_string(),
+        _ => from_path(path).first_or_octet_stream().to_string(),
+    };
+    let text = |s: Option<&std::ffi::OsStr>| s.map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+
+    Ok(HashMap::from([
+        (format!("{}_PATH", name), path.to_string_lossy().to_string()),
+        (format!("{}_NAME", name), text(path.file_name())),
+        (format!("{}_STEM", name), text(path.file_stem())),
+        (format!("{}_EXT", name), text(path.extension())),
+        (format!("{}_DIR", name), text(path.parent().map(|p| p.as_os_str()))),
+        (format!("{}_SIZE", name), metadata.len().to_string()),
+        (format!("{}_MTIME", name), mtime),
+        (format!("{}_MIME", name), mime_type),
+    ]))
+}
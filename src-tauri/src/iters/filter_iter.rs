@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use async_stream::stream;
+use serde_json::Value;
+use tokio_stream::StreamExt;
+use crate::iters::{get_iter, IterContext, ItemIter, ItemStream};
+use crate::models::{ApiError, IterFilter, IterPredicate, Result};
+use crate::utils::get_handlebars;
+
+impl ItemIter for IterFilter {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_filter = self.clone();
+        let env = ctx.env;
+        let seen = ctx.seen;
+        let http = ctx.http;
+        Box::pin(stream! {
+            let mut inner = get_iter(&iter_filter.iter, IterContext { env: env.clone(), seen: seen.clone(), http });
+            let mut skip = iter_filter.skip.unwrap_or(0);
+            let mut taken = 0;
+            while iter_filter.limit.is_none_or(|limit| taken < limit) {
+                let item = match inner.next().await {
+                    Some(Ok(item)) => item,
+                    Some(Err(e)) => { yield Err(e); continue; }
+                    None => break,
+                };
+                let mut cur_env = env.clone();
+                cur_env.extend(item.clone());
+
+                if let Some(filter) = &iter_filter.filter {
+                    match is_match(filter, &cur_env) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(e) => { yield Err(e); continue; }
+                    }
+                }
+                if let Some(distinct_by) = &iter_filter.distinct_by {
+                    let key = match get_handlebars(distinct_by, &cur_env) {
+                        Ok(key) => key,
+                        Err(e) => { yield Err(e); continue; }
+                    };
+                    if !seen.lock().unwrap().insert(key) {
+                        continue;
+                    }
+                }
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                taken += 1;
+                yield Ok(item);
+            }
+        })
+    }
+}
+
+fn is_match(filter: &IterPredicate, cur_env: &HashMap<String, String>) -> Result<bool> {
+    match filter {
+        IterPredicate::Template(template) => {
+            let rendered = get_handlebars(template, cur_env)?;
+            let rendered = rendered.trim();
+            Ok(!rendered.is_empty() && rendered != "false" && rendered != "0")
+        }
+        IterPredicate::JsonPath(path) => {
+            let json = Value::Array(vec![serde_json::to_value(cur_env)?]);
+            let matches = jsonpath_lib::select(&json, path)
+                .map_err(|e| ApiError::IterError(format!("{}: {:?}", path, e)))?;
+            Ok(!matches.is_empty())
+        }
+    }
+}
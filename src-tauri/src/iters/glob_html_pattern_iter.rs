@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use async_stream::stream;
+use scraper::{ElementRef, Html, Selector};
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::models::{ApiError, IterGlobHtmlPattern, ItemData, Result};
+use crate::iters::glob_json::{glob_files, read_text};
+use crate::utils::get_handlebars;
+
+struct Field {
+    name: String,
+    selector: Option<Selector>,
+    attr: Option<String>,
+}
+
+impl ItemIter for IterGlobHtmlPattern {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_glob_html_pattern = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let glob_pattern = match get_handlebars(&iter_glob_html_pattern.glob_pattern, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
+            };
+            let item_selector = match parse_selector(&iter_glob_html_pattern.item_selector) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
+            };
+            let mut fields = Vec::new();
+            for (k, v) in iter_glob_html_pattern.env_pattern.iter() {
+                match parse_field(k, v) {
+                    Ok(field) => fields.push(field),
+                    Err(e) => { yield Err(e); return; }
+                }
+            }
+
+            for p in glob_files(&glob_pattern) {
+                let p = match p {
+                    Ok(p) => p,
+                    Err(e) => { yield Err(e); continue; }
+                };
+                let text = match read_text(&p, iter_glob_html_pattern.encoding.as_deref()) {
+                    Ok(text) => text,
+                    Err(e) => { yield Err(e); continue; }
+                };
+                // `Html` is not Send, so a whole file is extracted before anything is yielded
+                for item in select_items(&text, &item_selector, &fields) {
+                    yield Ok(item);
+                }
+            }
+        })
+    }
+}
+
+fn parse_selector(s: &str) -> Result<Selector> {
+    Selector::parse(s).map_err(|e| ApiError::IterError(format!("{}: {:?}", s, e)))
+}
+
+fn parse_field(name: &str, pattern: &str) -> Result<Field> {
+    let (selector, attr) = match pattern.rsplit_once('@') {
+        Some((selector, attr)) => (selector.trim(), Some(attr.trim().to_string())),
+        None => (pattern.trim(), None),
+    };
+    let selector = if selector.is_empty() { None } else { Some(parse_selector(selector)?) };
+    Ok(Field { name: name.to_string(), selector, attr })
+}
+
+fn select_items(text: &str, item_selector: &Selector, fields: &[Field]) -> Vec<ItemData> {
+    let document = Html::parse_document(text);
+    let mut items = Vec::new();
+    for node in document.select(item_selector) {
+        let mut env_item = HashMap::new();
+        for field in fields {
+            let target = match &field.selector {
+                Some(selector) => node.select(selector).next(),
+                None => Some(node),
+            };
+            if let Some(val) = target.and_then(|target| field_val(target, field)) {
+                env_item.insert(field.name.clone(), val);
+            }
+        }
+        items.push(env_item);
+    }
+    items
+}
+
+fn field_val(target: ElementRef, field: &Field) -> Option<String> {
+    match &field.attr {
+        Some(attr) => target.value().attr(attr).map(|v| v.trim().to_string()),
+        None => Some(target.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")),
+    }
+}
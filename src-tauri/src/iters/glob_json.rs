@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use async_stream::stream;
+use glob::glob;
+use serde_json::Value;
+use tokio_stream::Stream;
+use crate::models::{ApiError, ItemData, Result};
+use crate::utils::{decode_bytes, get_handlebars, get_json_val};
+
+/// Paths matching `pattern`; a bad pattern or one that matches nothing comes back as a single error.
+pub fn glob_files(pattern: &str) -> Vec<Result<PathBuf>> {
+    let files: Vec<Result<PathBuf>> = match glob(pattern) {
+        Ok(paths) => paths
+            .map(|entry| entry.map_err(|e| ApiError::IterError(format!("{}: {}", pattern, e))))
+            .collect(),
+        Err(e) => vec![Err(ApiError::IterError(format!("{}: {}", pattern, e)))],
+    };
+    if files.is_empty() {
+        return vec![Err(ApiError::IterError(format!("{}: no file matches", pattern)))];
+    }
+    files
+}
+
+pub fn read_text(path: &Path, encoding: Option<&str>) -> Result<String> {
+    let bytes = std::fs::read(path).map_err(|e| ApiError::IterError(format!("{}: {}", path.display(), e)))?;
+    decode_bytes(&bytes, encoding).map_err(|e| ApiError::IterError(format!("{}: {:?}", path.display(), e)))
+}
+
+pub fn read_json(path: &Path) -> Result<Value> {
+    let json_str = read_text(path, None)?;
+    serde_json::from_str(&json_str).map_err(|e| ApiError::IterError(format!("{}: {}", path.display(), e)))
+}
+
+/// Parsed JSON of every file matching `pattern`, or of the first one only.
+pub fn glob_json(pattern: String, first_only: bool) -> impl Stream<Item = Result<(String, Value)>> + Send {
+    stream! {
+        for p in glob_files(&pattern) {
+            let p = match p {
+                Ok(p) => p,
+                Err(e) => { yield Err(e); continue; }
+            };
+            match read_json(&p) {
+                Ok(json) => yield Ok((p.to_string_lossy().to_string(), json)),
+                Err(e) => yield Err(e),
+            }
+            if first_only {
+                break;
+            }
+        }
+    }
+}
+
+pub fn select_json<'a>(json: &'a Value, path: &str, jsonpath: &str) -> Result<Vec<&'a Value>> {
+    jsonpath_lib::select(json, jsonpath).map_err(|e| ApiError::IterError(format!("{} {}: {:?}", path, jsonpath, e)))
+}
+
+pub fn render_values(patterns: &HashMap<String, String>, env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    let mut rendered = HashMap::new();
+    for (k, v) in patterns.iter() {
+        rendered.insert(k.clone(), get_handlebars(v, env)?);
+    }
+    Ok(rendered)
+}
+
+/// env name -> JSONPath applied to one item; paths that select nothing are left out.
+pub fn json_item(item: &Value, env_pattern: &HashMap<String, String>) -> ItemData {
+    let mut env_item = HashMap::new();
+    for (k, v) in env_pattern.iter() {
+        if let Some(j_val) = get_json_val(item, v) {
+            env_item.insert(k.to_string(), j_val);
+        }
+    }
+    env_item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_files_reports_no_match() {
+        let files = glob_files("/nonexistent-dir-for-test/*.json");
+        assert_eq!(files.len(), 1);
+        assert!(matches!(&files[0], Err(ApiError::IterError(e)) if e.ends_with("no file matches")));
+    }
+}
@@ -1,50 +1,42 @@
-use std::collections::HashMap;
-use std::pin::Pin;
 use async_stream::stream;
-use glob::glob;
-use tokio_stream::Stream;
-use crate::models::{ItemData, IterGlobJsonPattern};
-use crate::utils::{get_handlebars, get_json_val};
+use tokio_stream::StreamExt;
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::iters::glob_json::{glob_json, json_item, render_values, select_json};
+use crate::models::IterGlobJsonPattern;
+use crate::utils::get_handlebars;
 
-pub fn get_iter_glob_json_pattern(
-    iter_glob_json_pattern: IterGlobJsonPattern,
-    env: HashMap<String, String>,
-) -> Pin<Box<dyn Stream<Item = ItemData> + Send>> {
-    Box::pin(stream! {
-        let glob_pattern = iter_glob_json_pattern.glob_pattern;
-        let item_pattern = iter_glob_json_pattern.item_pattern;
-        let mut env_pattern = iter_glob_json_pattern.env_pattern;
-
-        let Ok(glob_pattern) = get_handlebars(&glob_pattern, &env) else { return ;};
-        let Ok(item_pattern) = get_handlebars(&item_pattern, &env) else { return ;};
-
-        for (_k, v) in env_pattern.iter_mut() {
-            let Ok(new_val) = get_handlebars(&v, &env) else { continue; };
-            *v = new_val;
-        }
-
-        let Ok(paths) = glob(&glob_pattern) else { return ;};
-        for entry in paths {
-            let Ok(p) = entry else { continue };
-            let Ok(json_str) = std::fs::read_to_string(p) else {
-                continue;
+impl ItemIter for IterGlobJsonPattern {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_glob_json_pattern = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let glob_pattern = match get_handlebars(&iter_glob_json_pattern.glob_pattern, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
             };
-            let Ok(json) = serde_json::from_str(&json_str) else {
-                continue;
+            let item_pattern = match get_handlebars(&iter_glob_json_pattern.item_pattern, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
             };
-            let Ok(item_vals) = jsonpath_lib::select(&json, &item_pattern) else {
-                continue;
+            let env_pattern = match render_values(&iter_glob_json_pattern.env_pattern, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
             };
-            for item in item_vals {
-                let mut env_item = HashMap::new();
-                for (k, v) in env_pattern.iter() {
-                    if let Some(j_val) = get_json_val(item, v) {
-                        env_item.insert(k.to_string(), j_val);
-                    }
+
+            let mut files = Box::pin(glob_json(glob_pattern, false));
+            while let Some(file) = files.next().await {
+                let (path, json) = match file {
+                    Ok(file) => file,
+                    Err(e) => { yield Err(e); continue; }
+                };
+                let item_vals = match select_json(&json, &path, &item_pattern) {
+                    Ok(item_vals) => item_vals,
+                    Err(e) => { yield Err(e); continue; }
+                };
+                for item in item_vals {
+                    yield Ok(json_item(item, &env_pattern));
                 }
-                yield env_item;
             }
-        }
-
-    })
+        })
+    }
 }
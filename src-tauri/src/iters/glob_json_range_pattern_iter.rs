@@ -1,40 +1,43 @@
 use std::collections::HashMap;
-use std::pin::Pin;
 use async_stream::stream;
-use glob::glob;
-use tokio_stream::Stream;
-use crate::models::{ItemData, IterJsonRangePattern};
-use crate::utils::{get_handlebars, get_json_val};
+use tokio_stream::StreamExt;
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::iters::glob_json::glob_json;
+use crate::iters::num_range::{NumRange, RangeFields};
+use crate::models::{ApiError, IterJsonRangePattern};
+use crate::utils::get_handlebars;
 
-pub fn get_iter_glob_json_range_pattern(
-    iter_glob_json_range_pattern: IterJsonRangePattern,
-    env: HashMap<String, String>,
-) -> Pin<Box<dyn Stream<Item = ItemData> + Send>> {
-    Box::pin(stream! {
-        let name = iter_glob_json_range_pattern.name;
-        let file_pattern = iter_glob_json_range_pattern.file_pattern;
-        let offset_pattern = iter_glob_json_range_pattern.offset_pattern;
-        let take_pattern = iter_glob_json_range_pattern.take_pattern;
+impl ItemIter for IterJsonRangePattern {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_glob_json_range_pattern = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let name = iter_glob_json_range_pattern.name.clone();
+            let file_pattern = match get_handlebars(&iter_glob_json_range_pattern.file_pattern, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
+            };
+            let (path, json) = match Box::pin(glob_json(file_pattern, true)).next().await {
+                Some(Ok(file)) => file,
+                Some(Err(e)) => { yield Err(e); return; }
+                None => return,
+            };
 
-        let Ok(file_pattern) = get_handlebars(&file_pattern, &env) else { return ; };
-        let Ok(offset_pattern) = get_handlebars(&offset_pattern, &env) else { return ;};
-        let Ok(take_pattern) = get_handlebars(&take_pattern, &env) else { return; };
-
-        let Ok(mut paths) = glob(&file_pattern) else { return ; };
-        let Some(entry) = paths.next() else { return ; };
-        let Ok(p) = entry else { return ; };
-        let Ok(json_str) = std::fs::read_to_string(p) else { return; };
-        let Ok(json) = serde_json::from_str(&json_str) else { return ; };
-        let offset_str = get_json_val(&json, &offset_pattern).unwrap_or(offset_pattern);
-        let take_str = get_json_val(&json, &take_pattern).unwrap_or(take_pattern);
-        let Ok(offset) = offset_str.parse::<usize>() else { return ;};
-        let Ok(take) = take_str.parse::<usize>() else {return ;};
-
-        let start = offset;
-        let end = offset + take;
-        for i in start..end {
-            yield HashMap::from([(name.to_string(), i.to_string())]);
-        }
-    })
+            let fields = RangeFields {
+                offset: &iter_glob_json_range_pattern.offset_pattern,
+                take: iter_glob_json_range_pattern.take_pattern.as_deref(),
+                end: iter_glob_json_range_pattern.end_pattern.as_deref(),
+                step: iter_glob_json_range_pattern.step_pattern.as_deref(),
+                inclusive: iter_glob_json_range_pattern.inclusive,
+            };
+            let range = match NumRange::resolve(&fields, &env, Some(&json)) {
+                Ok(range) => range,
+                Err(ApiError::IterError(e)) => { yield Err(ApiError::IterError(format!("{}: {}", path, e))); return; }
+                Err(e) => { yield Err(e); return; }
+            };
+            for i in range.values() {
+                yield Ok(HashMap::from([(name.to_string(), i.to_string())]));
+            }
+        })
+    }
 }
-
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Read;
+use std::str::FromStr;
+use std::time::Duration;
+use async_stream::stream;
+use flate2::read::GzDecoder;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use roxmltree::{Document, Node};
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::models::{ApiError, IterGlobXmlPattern, ItemData, Result};
+use crate::iters::glob_json::glob_files;
+use crate::tasks::http_task::send_header;
+use crate::tasks::task::TaskContext;
+use crate::utils::get_handlebars;
+
+const SITEMAP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Items of one document, or the child sitemaps of a sitemap index.
+enum XmlPage {
+    Items(Vec<ItemData>),
+    SitemapIndex(Vec<String>),
+}
+
+impl ItemIter for IterGlobXmlPattern {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_glob_xml_pattern = self.clone();
+        let env = ctx.env;
+        let http = ctx.http;
+        Box::pin(stream! {
+            let glob_pattern = match get_handlebars(&iter_glob_xml_pattern.glob_pattern, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
+            };
+            let item_path = iter_glob_xml_pattern.item_path.clone();
+            let env_pattern = iter_glob_xml_pattern.env_pattern.clone();
+            let follow = iter_glob_xml_pattern.follow_sitemap_index.unwrap_or(true);
+
+            let mut queue = VecDeque::new();
+            for p in glob_files(&glob_pattern) {
+                match p {
+                    Ok(p) => queue.push_back(p.to_string_lossy().to_string()),
+                    Err(e) => yield Err(e),
+                }
+            }
+
+            let mut visited = HashSet::new();
+            while let Some(source) = queue.pop_front() {
+                if !visited.insert(source.clone()) {
+                    continue;
+                }
+                let bytes = match read_source(&source, &env, &http).await {
+                    Ok(bytes) => bytes,
+                    Err(e) => { yield Err(ApiError::IterError(format!("{}: {:?}", source, e))); continue; }
+                };
+                let text = match gunzip(bytes) {
+                    Ok(text) => text,
+                    Err(e) => { yield Err(ApiError::IterError(format!("{}: {:?}", source, e))); continue; }
+                };
+                // `Document` borrows `text`, so a whole file is extracted before anything is yielded
+                match parse_page(&text, &item_path, &env_pattern, follow) {
+                    Ok(XmlPage::Items(items)) => {
+                        for item in items {
+                            yield Ok(item);
+                        }
+                    }
+                    Ok(XmlPage::SitemapIndex(children)) => queue.extend(children),
+                    Err(e) => yield Err(ApiError::IterError(format!("{}: {:?}", source, e))),
+                }
+            }
+        })
+    }
+}
+
+/// Remote child sitemaps go through the step's client, so the rate limit, cookies, auth and
+/// setting headers apply; preview has no client and fetches nothing.
+async fn read_source(source: &str, env: &HashMap<String, String>, http: &Option<TaskContext>) -> Result<Vec<u8>> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        return Ok(std::fs::read(source)?);
+    }
+    let Some(http) = http else {
+        return Err(ApiError::ScrapingError("remote sitemaps are not fetched in preview".to_string()));
+    };
+    if let Some(rate_limit) = &http.rate_limit {
+        http.rate_limiter.acquire_url(source, rate_limit).await?;
+    }
+    let mut header = HeaderMap::new();
+    for (k, v) in http.header.iter() {
+        let nm = HeaderName::from_str(k.as_str())?;
+        let val = HeaderValue::from_str(&get_handlebars(v, env)?)?;
+        header.insert(nm, val);
+    }
+    let token = match &http.auth {
+        Some(auth) => Some(auth.token().await?),
+        None => None,
+    };
+    let auth = http.auth.as_deref().zip(token.as_deref());
+    let header = send_header(header, source, auth, &http.cookie_jar).await?;
+
+    let res = http.client.get(source).headers(header).timeout(SITEMAP_TIMEOUT).send().await?;
+    if !res.status().is_success() {
+        return Err(ApiError::HttpStatus(res.status().as_u16(), None, source.to_string()));
+    }
+    Ok(res.bytes().await?.to_vec())
+}
+
+fn gunzip(bytes: Vec<u8>) -> Result<String> {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut text = String::new();
+        GzDecoder::new(bytes.as_slice()).read_to_string(&mut text)?;
+        return Ok(text);
+    }
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+fn parse_page(text: &str, item_path: &str, env_pattern: &HashMap<String, String>, follow: bool) -> Result<XmlPage> {
+    let doc = Document::parse(text).map_err(|e| ApiError::ParseError(e.to_string()))?;
+    let root = doc.root_element();
+    if follow && root.tag_name().name() == "sitemapindex" {
+        let children = select_nodes(doc.root(), "sitemapindex/sitemap/loc")
+            .iter()
+            .map(node_text)
+            .filter(|loc| !loc.is_empty())
+            .collect();
+        return Ok(XmlPage::SitemapIndex(children));
+    }
+
+    let mut items = Vec::new();
+    for node in select_nodes(doc.root(), item_path) {
+        let mut env_item = HashMap::new();
+        for (k, v) in env_pattern.iter() {
+            if let Some(val) = select_val(node, v) {
+                env_item.insert(k.to_string(), val);
+            }
+        }
+        items.push(env_item);
+    }
+    Ok(XmlPage::Items(items))
+}
+
+/// `a/b` walks children, an empty segment (`a//b`) searches all descendants.
+fn select_nodes<'a, 'input>(from: Node<'a, 'input>, path: &str) -> Vec<Node<'a, 'input>> {
+    let mut nodes = vec![from];
+    let (mut descendant, path) = match path.strip_prefix("//") {
+        Some(path) => (true, path),
+        None => (false, path.trim_start_matches('/')),
+    };
+    for seg in path.split('/') {
+        if seg.is_empty() {
+            descendant = true;
+            continue;
+        }
+        let mut next = Vec::new();
+        for node in nodes.iter() {
+            if descendant {
+                next.extend(node.descendants().skip(1).filter(|n| is_named(n, seg)));
+            } else {
+                next.extend(node.children().filter(|n| is_named(n, seg)));
+            }
+        }
+        nodes = next;
+        descendant = false;
+    }
+    nodes
+}
+
+fn select_val(item: Node, path: &str) -> Option<String> {
+    let (path, attr) = match path.rsplit_once('@') {
+        Some((path, attr)) => (path.trim_end_matches('/'), Some(attr)),
+        None => (path.trim_end_matches("/text()"), None),
+    };
+    let node = if path.is_empty() || path == "." {
+        item
+    } else {
+        *select_nodes(item, path).first()?
+    };
+    match attr {
+        Some(attr) => node
+            .attributes()
+            .find(|a| a.name() == local_name(attr))
+            .map(|a| a.value().trim().to_string()),
+        None => Some(node_text(&node)),
+    }
+}
+
+fn is_named(node: &Node, seg: &str) -> bool {
+    node.is_element() && (seg == "*" || node.tag_name().name() == local_name(seg))
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn node_text(node: &Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
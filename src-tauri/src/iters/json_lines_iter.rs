@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::path::Path;
+use async_stream::stream;
+use serde_json::Value;
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::models::{ApiError, IterJsonLines};
+use crate::iters::glob_json::read_text;
+use crate::utils::{get_handlebars, get_json_val};
+
+impl ItemIter for IterJsonLines {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_json_lines = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let path = match get_handlebars(&iter_json_lines.path, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
+            };
+            let text = match read_text(Path::new(&path), iter_json_lines.encoding.as_deref()) {
+                Ok(text) => text,
+                Err(e) => { yield Err(e); return; }
+            };
+
+            for (idx, line) in text.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let json: Value = match serde_json::from_str(line) {
+                    Ok(json) => json,
+                    Err(e) => { yield Err(ApiError::IterError(format!("{} line {}: {}", path, idx + 1, e))); continue; }
+                };
+                let mut env_item = HashMap::new();
+                match &iter_json_lines.env_pattern {
+                    Some(env_pattern) => {
+                        for (k, v) in env_pattern.iter() {
+                            if let Some(j_val) = get_json_val(&json, v) {
+                                env_item.insert(k.to_string(), j_val);
+                            }
+                        }
+                    }
+                    None => {
+                        let Value::Object(map) = &json else {
+                            yield Err(ApiError::IterError(format!("{} line {}: not an object", path, idx + 1)));
+                            continue;
+                        };
+                        for (k, v) in map.iter() {
+                            let val = match v {
+                                Value::String(s) => s.trim().to_string(),
+                                _ => v.to_string(),
+                            };
+                            env_item.insert(k.to_string(), val);
+                        }
+                    }
+                }
+                yield Ok(env_item);
+            }
+        })
+    }
+}
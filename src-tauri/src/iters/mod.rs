@@ -4,3 +4,44 @@ pub mod range_pattern_iter;
 pub mod range_iter;
 pub mod glob_json_range_pattern_iter;
 pub mod glob_json_pattern_iter;
+pub mod csv_iter;
+pub mod json_lines_iter;
+pub mod filter_iter;
+pub mod date_range_iter;
+pub mod glob_html_pattern_iter;
+pub mod glob_xml_pattern_iter;
+pub mod files_iter;
+pub mod num_range;
+pub mod glob_json;
+pub mod registry;
+
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio_stream::Stream;
+use crate::models::{ItemData, Result, TaskIter};
+use crate::tasks::task::TaskContext;
+
+pub type ItemStream = Pin<Box<dyn Stream<Item = Result<ItemData>> + Send>>;
+
+/// `distinct_by` values seen so far, kept per iterator position for a whole step run.
+pub type SeenSet = Arc<Mutex<HashSet<String>>>;
+
+#[derive(Clone, Debug, Default)]
+pub struct IterContext {
+    pub env: HashMap<String, String>,
+    pub seen: SeenSet,
+    /// client, limiter, headers and auth for iterators that fetch remote sources; None in preview
+    pub http: Option<TaskContext>,
+}
+
+/// An iterator config that can be turned into a stream of items for the current env.
+///
+/// A new kind needs a config struct implementing this, a `TaskIter` variant and a line in `registry`.
+pub trait ItemIter {
+    fn build(&self, ctx: IterContext) -> ItemStream;
+}
+
+pub fn get_iter(task_iter: &TaskIter, ctx: IterContext) -> ItemStream {
+    task_iter.item_iter().build(ctx)
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use serde_json::Value;
+use crate::models::{ApiError, Result};
+use crate::utils::{get_handlebars, get_json_val};
+
+/// Range fields as written in the setting; each is a template, then a JSONPath into `json` when one is given.
+pub struct RangeFields<'a> {
+    pub offset: &'a str,
+    pub take: Option<&'a str>,
+    pub end: Option<&'a str>,
+    pub step: Option<&'a str>,
+    pub inclusive: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumRange {
+    pub start: i64,
+    pub end: Option<i64>,
+    pub take: Option<i64>,
+    pub step: i64,
+    pub inclusive: bool,
+}
+
+impl NumRange {
+    pub fn resolve(fields: &RangeFields, env: &HashMap<String, String>, json: Option<&Value>) -> Result<Self> {
+        let start = resolve_num("offset", fields.offset, env, json)?;
+        let end = match fields.end {
+            Some(end) => Some(resolve_num("end", end, env, json)?),
+            None => None,
+        };
+        let take = match (end, fields.take) {
+            (None, Some(take)) => Some(resolve_num("take", take, env, json)?),
+            (None, None) => return Err(ApiError::IterError("take or end is required".to_string())),
+            _ => None,
+        };
+        let step = match fields.step {
+            Some(step) => resolve_num("step", step, env, json)?,
+            None if end.is_some_and(|end| end < start) => -1,
+            None => 1,
+        };
+        if step == 0 {
+            return Err(ApiError::IterError("step must not be 0".to_string()));
+        }
+        Ok(NumRange { start, end, take, step, inclusive: fields.inclusive.unwrap_or(false) })
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = i64> + Send + '_ {
+        let mut i = 0;
+        std::iter::from_fn(move || {
+            if self.take.is_some_and(|take| i >= take) {
+                return None;
+            }
+            let cur = self.start.checked_add(self.step.checked_mul(i)?)?;
+            if let Some(end) = self.end {
+                let before_end = match (self.step > 0, self.inclusive) {
+                    (true, true) => cur <= end,
+                    (true, false) => cur < end,
+                    (false, true) => cur >= end,
+                    (false, false) => cur > end,
+                };
+                if !before_end {
+                    return None;
+                }
+            }
+            i += 1;
+            Some(cur)
+        })
+    }
+}
+
+fn resolve_num(field: &str, pattern: &str, env: &HashMap<String, String>, json: Option<&Value>) -> Result<i64> {
+    let rendered = get_handlebars(pattern, env)?;
+    let val = json.and_then(|json| get_json_val(json, &rendered)).unwrap_or(rendered);
+    val.trim()
+        .parse::<i64>()
+        .map_err(|_| ApiError::IterError(format!("{} {}: not a number: {:?}", field, pattern, val)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields<'a>(offset: &'a str, take: Option<&'a str>, end: Option<&'a str>, step: Option<&'a str>, inclusive: Option<bool>) -> RangeFields<'a> {
+        RangeFields { offset, take, end, step, inclusive }
+    }
+
+    fn values(fields: &RangeFields) -> Vec<i64> {
+        NumRange::resolve(fields, &HashMap::new(), None).unwrap().values().collect()
+    }
+
+    #[test]
+    fn take_counts_values() {
+        assert_eq!(values(&fields("3", Some("4"), None, None, None)), [3, 4, 5, 6]);
+        assert_eq!(values(&fields("0", Some("3"), None, Some("5"), None)), [0, 5, 10]);
+        assert_eq!(values(&fields("0", Some("0"), None, None, None)), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn end_wins_over_take() {
+        assert_eq!(values(&fields("1", Some("10"), Some("4"), None, None)), [1, 2, 3]);
+    }
+
+    #[test]
+    fn end_is_exclusive_unless_inclusive() {
+        assert_eq!(values(&fields("1", None, Some("4"), None, None)), [1, 2, 3]);
+        assert_eq!(values(&fields("1", None, Some("4"), None, Some(true))), [1, 2, 3, 4]);
+        assert_eq!(values(&fields("0", None, Some("10"), Some("5"), Some(true))), [0, 5, 10]);
+    }
+
+    #[test]
+    fn negative_step() {
+        assert_eq!(values(&fields("10", None, Some("0"), Some("-5"), None)), [10, 5]);
+        assert_eq!(values(&fields("10", None, Some("0"), Some("-5"), Some(true))), [10, 5, 0]);
+        assert_eq!(values(&fields("2", Some("3"), None, Some("-1"), None)), [2, 1, 0]);
+    }
+
+    #[test]
+    fn step_defaults_to_minus_one_when_end_is_below_offset() {
+        assert_eq!(values(&fields("3", None, Some("0"), None, None)), [3, 2, 1]);
+        assert_eq!(values(&fields("3", None, Some("0"), None, Some(true))), [3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn rejects_zero_step_and_missing_bounds() {
+        let err = NumRange::resolve(&fields("0", Some("3"), None, Some("0"), None), &HashMap::new(), None);
+        assert!(matches!(err, Err(ApiError::IterError(e)) if e.contains("step must not be 0")));
+        let err = NumRange::resolve(&fields("0", None, None, None, None), &HashMap::new(), None);
+        assert!(matches!(err, Err(ApiError::IterError(e)) if e.contains("take or end is required")));
+        let err = NumRange::resolve(&fields("x", Some("3"), None, None, None), &HashMap::new(), None);
+        assert!(matches!(err, Err(ApiError::IterError(e)) if e.contains("not a number")));
+    }
+
+    #[test]
+    fn resolves_templates_and_json_paths() {
+        let env = HashMap::from([("FIRST".to_string(), "2".to_string())]);
+        let json = json!({ "paging": { "last": 5, "size": "2" } });
+        let range = NumRange::resolve(&fields("{{FIRST}}", None, Some("$.paging.last"), Some("$.paging.size"), Some(true)), &env, Some(&json)).unwrap();
+        assert_eq!(range, NumRange { start: 2, end: Some(5), take: None, step: 2, inclusive: true });
+        assert_eq!(range.values().collect::<Vec<_>>(), [2, 4]);
+    }
+}
@@ -1,25 +1,30 @@
 use std::collections::HashMap;
-use std::pin::Pin;
 use async_stream::stream;
-use glob::glob;
-use tokio_stream::Stream;
-use crate::models::{ItemData, IterPattern};
+use tokio_stream::StreamExt;
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::iters::glob_json::{glob_json, select_json};
+use crate::models::IterPattern;
 
-pub fn get_iter_pattern(iter_pattern: IterPattern) -> Pin<Box<dyn Stream<Item = ItemData> + Send>> {
-    Box::pin(stream!{
-        let name = iter_pattern.name;
-        let glob_pattern = iter_pattern.glob_pattern;
-        let content_pattern = iter_pattern.content_pattern;
-        if let Ok(paths) = glob(&glob_pattern) {
-            for entry in paths {
-                let Ok(p) = entry else { continue };
-                let Ok(json_str) = std::fs::read_to_string(p) else { continue; };
-                let Ok(json) = serde_json::from_str(&json_str) else { continue; };
-                let Ok(values) = jsonpath_lib::select(&json, &content_pattern) else { continue; };
+impl ItemIter for IterPattern {
+    fn build(&self, _ctx: IterContext) -> ItemStream {
+        let iter_pattern = self.clone();
+        Box::pin(stream! {
+            let name = iter_pattern.name;
+            let content_pattern = iter_pattern.content_pattern;
+            let mut files = Box::pin(glob_json(iter_pattern.glob_pattern, false));
+            while let Some(file) = files.next().await {
+                let (path, json) = match file {
+                    Ok(file) => file,
+                    Err(e) => { yield Err(e); continue; }
+                };
+                let values = match select_json(&json, &path, &content_pattern) {
+                    Ok(values) => values,
+                    Err(e) => { yield Err(e); continue; }
+                };
                 for val in values {
-                    yield HashMap::from([(name.to_string(), val.to_string())]);
+                    yield Ok(HashMap::from([(name.to_string(), val.to_string())]));
                 }
             }
-        }
-    })
+        })
+    }
 }
@@ -1,24 +1,30 @@
 use std::collections::HashMap;
-use std::pin::Pin;
 use async_stream::stream;
-use tokio_stream::Stream;
-use crate::models::{ItemData, IterRange};
-use crate::utils::{get_handlebars};
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::iters::num_range::{NumRange, RangeFields};
+use crate::models::{ApiError, IterRange};
 
-pub fn get_iter_range(iter_range: IterRange, env: HashMap<String, String>) -> Pin<Box<dyn Stream<Item = ItemData> + Send>> {
-    Box::pin(stream! {
-        let name = iter_range.name;
-        let offset_pattern = iter_range.offset;
-        let take_pattern = iter_range.take;
-        let Ok(offset_str) = get_handlebars(&offset_pattern, &env) else { return ; };
-        let Ok(take_str) = get_handlebars(&take_pattern, &env) else { return ; };
-        let Ok(offset) = offset_str.parse::<usize>() else { return ; };
-        let Ok(take) = take_str.parse::<usize>() else { return ;};
-        let start = offset;
-        let end = offset + take;
-        for i in start..end {
-            yield HashMap::from([(name.to_string(), i.to_string())]);
-        }
-    })
+impl ItemIter for IterRange {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_range = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let name = iter_range.name.clone();
+            let fields = RangeFields {
+                offset: &iter_range.offset,
+                take: iter_range.take.as_deref(),
+                end: iter_range.end.as_deref(),
+                step: iter_range.step.as_deref(),
+                inclusive: iter_range.inclusive,
+            };
+            let range = match NumRange::resolve(&fields, &env, None) {
+                Ok(range) => range,
+                Err(ApiError::IterError(e)) => { yield Err(ApiError::IterError(format!("{}: {}", name, e))); return; }
+                Err(e) => { yield Err(e); return; }
+            };
+            for i in range.values() {
+                yield Ok(HashMap::from([(name.to_string(), i.to_string())]));
+            }
+        })
+    }
 }
-
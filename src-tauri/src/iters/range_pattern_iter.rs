@@ -1,44 +1,42 @@
 use std::collections::HashMap;
 use std::path::Path;
-use std::pin::Pin;
 use async_stream::stream;
-use tokio_stream::Stream;
-use crate::models::{ItemData, IterRangePattern};
-use crate::utils::{get_handlebars, get_json_val};
-pub fn get_iter_range_pattern(
-    iter_range_pattern: IterRangePattern,
-    env: HashMap<String, String>,
-) -> Pin<Box<dyn Stream<Item = ItemData> + Send>> {
-    Box::pin(stream! {
-        let name = iter_range_pattern.name;
-        let glob_pattern = iter_range_pattern.glob_pattern;
-        let Ok(file_path) = get_handlebars(&glob_pattern, &env) else { return ; };
-        let Ok(mut offset_str) = get_handlebars(&iter_range_pattern.offset, &env) else { return ;};
-        let Ok(mut take_str) = get_handlebars(&iter_range_pattern.take, &env) else { return ;};
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::iters::glob_json::read_json;
+use crate::iters::num_range::{NumRange, RangeFields};
+use crate::models::{ApiError, IterRangePattern};
+use crate::utils::get_handlebars;
 
-        let Ok(json_str) = std::fs::read_to_string(Path::new(&file_path)) else { return ; };
-        let Ok(json) = serde_json::from_str(&json_str) else { return; };
-        match get_json_val(&json, &offset_str) {
-            Some(val) => {
-                offset_str = val;
-            }
-            None => {}
-        }
-        match get_json_val(&json, &take_str) {
-            Some(val) => {
-                take_str = val;
-            }
-            None => {}
-        }
-
-        let Ok(offset) = offset_str.parse::<usize>() else { return ; };
-        let Ok(take) = take_str.parse::<usize>() else { return ; };
-        let start = offset;
-        let end = offset + take;
+impl ItemIter for IterRangePattern {
+    fn build(&self, ctx: IterContext) -> ItemStream {
+        let iter_range_pattern = self.clone();
+        let env = ctx.env;
+        Box::pin(stream! {
+            let name = iter_range_pattern.name.clone();
+            let file_path = match get_handlebars(&iter_range_pattern.glob_pattern, &env) {
+                Ok(v) => v,
+                Err(e) => { yield Err(e); return; }
+            };
+            let json = match read_json(Path::new(&file_path)) {
+                Ok(json) => json,
+                Err(e) => { yield Err(e); return; }
+            };
 
-        for i in start..end {
-            yield HashMap::from([(name.to_string(), i.to_string())]);
-        }
-    })
+            let fields = RangeFields {
+                offset: &iter_range_pattern.offset,
+                take: iter_range_pattern.take.as_deref(),
+                end: iter_range_pattern.end.as_deref(),
+                step: iter_range_pattern.step.as_deref(),
+                inclusive: iter_range_pattern.inclusive,
+            };
+            let range = match NumRange::resolve(&fields, &env, Some(&json)) {
+                Ok(range) => range,
+                Err(ApiError::IterError(e)) => { yield Err(ApiError::IterError(format!("{}: {}", file_path, e))); return; }
+                Err(e) => { yield Err(e); return; }
+            };
+            for i in range.values() {
+                yield Ok(HashMap::from([(name.to_string(), i.to_string())]));
+            }
+        })
+    }
 }
-
@@ -0,0 +1,22 @@
+use crate::iters::ItemIter;
+use crate::models::TaskIter;
+
+impl TaskIter {
+    pub fn item_iter(&self) -> &dyn ItemIter {
+        match self {
+            TaskIter::Range(it) => it,
+            TaskIter::Pattern(it) => it,
+            TaskIter::RangePattern(it) => it,
+            TaskIter::Vec(it) => it,
+            TaskIter::GlobJsonPattern(it) => it,
+            TaskIter::GlobJsonRangePattern(it) => it,
+            TaskIter::Csv(it) => it,
+            TaskIter::JsonLines(it) => it,
+            TaskIter::Filter(it) => it,
+            TaskIter::DateRange(it) => it,
+            TaskIter::GlobHtmlPattern(it) => it,
+            TaskIter::GlobXmlPattern(it) => it,
+            TaskIter::Files(it) => it,
+        }
+    }
+}
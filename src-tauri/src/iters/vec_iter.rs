@@ -1,14 +1,16 @@
 use std::collections::HashMap;
-use std::pin::Pin;
 use async_stream::stream;
-use tokio_stream::Stream;
-use crate::models::{IterList, ItemData};
+use crate::iters::{IterContext, ItemIter, ItemStream};
+use crate::models::IterList;
 
-pub fn get_iter_vec(iter_vec: IterList) -> Pin<Box<dyn Stream<Item = ItemData> + Send>> {
-    Box::pin(stream! {
-        let name = iter_vec.name;
-        for v in iter_vec.val.iter() {
-            yield HashMap::from([(name.clone(), v.clone())]);
-        }
-    })
-}
\ No newline at end of file
+impl ItemIter for IterList {
+    fn build(&self, _ctx: IterContext) -> ItemStream {
+        let iter_vec = self.clone();
+        Box::pin(stream! {
+            let name = iter_vec.name;
+            for v in iter_vec.val.iter() {
+                yield Ok(HashMap::from([(name.clone(), v.clone())]));
+            }
+        })
+    }
+}
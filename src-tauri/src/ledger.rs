@@ -0,0 +1,82 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{ItemData, Result};
+
+pub const LEDGER_DONE: &str = "done";
+pub const LEDGER_FAILED: &str = "failed";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LedgerEntry {
+    pub key: String,
+    pub status: String,
+    pub timestamp: String,
+    pub error: Option<String>,
+}
+
+pub struct Ledger {
+    pub file: Mutex<File>,
+    pub entries: HashMap<String, LedgerEntry>,
+}
+
+impl Ledger {
+    pub fn open(path: &str) -> Result<Self> {
+        let p = Path::new(path);
+        if let Some(parent) = p.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+
+        let mut entries = HashMap::new();
+        if p.exists() {
+            let text = std::fs::read_to_string(p)?;
+            for line in text.lines() {
+                // a crashed run may leave a partial last line
+                let Ok(entry) = serde_json::from_str::<LedgerEntry>(line) else { continue; };
+                entries.insert(entry.key.clone(), entry);
+            }
+        }
+
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(p)?;
+        Ok(Ledger { file: Mutex::new(file), entries })
+    }
+
+    pub fn is_done(&self, key: &str) -> bool {
+        self.entries
+            .get(key)
+            .map(|entry| entry.status == LEDGER_DONE)
+            .unwrap_or(false)
+    }
+
+    pub fn record(&self, key: String, error: Option<String>) -> Result<()> {
+        let status = if error.is_none() { LEDGER_DONE } else { LEDGER_FAILED };
+        let entry = LedgerEntry {
+            key,
+            status: status.to_string(),
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            error,
+        };
+        let line = serde_json::to_string(&entry)?;
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+pub fn task_key(vals: &[Option<ItemData>]) -> String {
+    let mut key = BTreeMap::new();
+    for val in vals.iter().flatten() {
+        key.extend(val.iter());
+    }
+    serde_json::to_string(&key).unwrap_or_default()
+}
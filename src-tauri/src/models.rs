@@ -3,11 +3,16 @@ use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, skip_serializing_none};
 use specta::Type;
 use std::collections::HashMap;
-use std::sync::atomic::AtomicU8;
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::Arc;
 use thiserror::Error;
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::{watch, RwLock, Semaphore};
+use tokio_util::sync::CancellationToken;
 use crate::tasks::task::Job;
+use crate::retry::RetryPolicy;
+use crate::rate_limit::RateLimit;
+use crate::cookies::CookieConfig;
+use crate::auth::AuthConfig;
+use crate::stats::StepCounters;
 
 pub type Result<T> = std::result::Result<T, ApiError>;
 pub type ItemData = HashMap<String, String>;
@@ -26,6 +31,109 @@ pub enum TaskIter {
     Vec(IterList),
     GlobJsonPattern(IterGlobJsonPattern),
     GlobJsonRangePattern(IterJsonRangePattern),
+    Csv(IterCsv),
+    JsonLines(IterJsonLines),
+    Filter(IterFilter),
+    DateRange(IterDateRange),
+    GlobHtmlPattern(IterGlobHtmlPattern),
+    GlobXmlPattern(IterGlobXmlPattern),
+    Files(IterFiles),
+}
+
+/// Yields `{name}_PATH`, `_NAME`, `_STEM`, `_EXT`, `_DIR`, `_SIZE`, `_MTIME` and `_MIME` per file.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct IterFiles {
+    pub name: String,
+    pub dir: String,
+    pub recursive: Option<bool>,
+    pub max_depth: Option<usize>,
+    /// glob patterns matched against the path relative to `dir`, e.g. `**/*.pdf`
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct IterGlobXmlPattern {
+    pub glob_pattern: String,
+    /// element path from the root such as `urlset/url` or `rss/channel/item`; `//item` matches at any depth
+    pub item_path: String,
+    /// env name -> path inside the item (`loc`, `enclosure/@url`, `@id`); namespace prefixes are ignored
+    pub env_pattern: HashMap<String, String>,
+    /// load the child sitemaps of a `sitemapindex`, true when omitted
+    pub follow_sitemap_index: Option<bool>,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct IterGlobHtmlPattern {
+    pub glob_pattern: String,
+    /// CSS selector; one item per matching node
+    pub item_selector: String,
+    /// env name -> `selector` (text), `selector@attr`, `@attr` of the item, or "" for the item text
+    pub env_pattern: HashMap<String, String>,
+    pub encoding: Option<String>,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct IterDateRange {
+    pub name: String,
+    /// `2025-01-01`, `2025-01-01 09:00:00`, `today` or `now`; templated from env
+    pub start: String,
+    pub end: String,
+    pub step: Option<u32>,
+    pub unit: Option<DateUnit>,
+    /// chrono format string, `%Y-%m-%d` when omitted
+    pub format: Option<String>,
+    /// IANA name such as `Asia/Seoul`; local time when omitted
+    pub timezone: Option<String>,
+    /// include `end` itself; false when omitted, like the other ranges
+    pub inclusive: Option<bool>,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub enum DateUnit {
+    Hours,
+    #[default]
+    Days,
+    Months,
+}
+
+/// Wraps another iterator; `filter`, then `distinct_by`, then `skip`/`limit` are applied in that order.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct IterFilter {
+    pub iter: Box<TaskIter>,
+    pub filter: Option<IterPredicate>,
+    /// template over the item; items rendering an already seen value are dropped for the rest of the step run
+    pub distinct_by: Option<String>,
+    pub skip: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub enum IterPredicate {
+    /// kept when the rendered text is not empty, "false" or "0"
+    Template(String),
+    /// kept when the JSONPath matches `[item]`, e.g. `$[?(@.TYPE == 'B')]`
+    JsonPath(String),
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct IterCsv {
+    pub path: String,
+    pub delimiter: Option<String>,
+    pub encoding: Option<String>,
+    pub has_header: Option<bool>,
+    /// column names when the file has no header row
+    pub columns: Option<Vec<String>>,
+    /// env name -> column name; all columns by their own name when omitted
+    pub env_map: Option<HashMap<String, String>>,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct IterJsonLines {
+    pub path: String,
+    pub encoding: Option<String>,
+    /// env name -> JSONPath; top-level fields by their own name when omitted
+    pub env_pattern: Option<HashMap<String, String>>,
 }
 
 #[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
@@ -40,14 +148,23 @@ pub struct IterJsonRangePattern {
     pub name: String,
     pub file_pattern: String,
     pub offset_pattern: String,
-    pub take_pattern: String,
+    pub take_pattern: Option<String>,
+    pub end_pattern: Option<String>,
+    pub step_pattern: Option<String>,
+    pub inclusive: Option<bool>,
 }
 
 #[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct IterRange {
     pub name: String,
     pub offset: String,
-    pub take: String,
+    /// number of values; ignored when `end` is set
+    pub take: Option<String>,
+    /// exclusive unless `inclusive`
+    pub end: Option<String>,
+    /// may be negative; 1 when omitted, or -1 when `end` is below `offset`
+    pub step: Option<String>,
+    pub inclusive: Option<bool>,
 }
 
 #[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
@@ -62,7 +179,10 @@ pub struct IterRangePattern {
     pub name: String,
     pub glob_pattern: String,
     pub offset: String,
-    pub take: String,
+    pub take: Option<String>,
+    pub end: Option<String>,
+    pub step: Option<String>,
+    pub inclusive: Option<bool>,
 }
 
 #[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
@@ -74,12 +194,42 @@ pub struct IterList {
 
 
 
+/// How a step combines its `task_iters`.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub enum IterMode {
+    /// Nested loops; later iterators see the values of earlier ones.
+    #[default]
+    Product,
+    /// The i-th items of all iterators together; stops at the shortest.
+    Zip,
+    /// Like `Zip`, but runs until the longest ends, leaving finished iterators' values unset.
+    ZipLongest,
+    /// Every item of the first iterator, then every item of the next, and so on.
+    Chain,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub enum IterErrorPolicy {
+    #[default]
+    Skip,
+    Abort,
+}
+
 #[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct Step {
     pub name: String,
     pub task_iters: Vec<TaskIter>,
+    #[serde(default)]
+    pub iter_mode: IterMode,
     pub job: Job,
     pub concurrency_limit: usize,
+    pub ledger: Option<String>,
+    pub retry: Option<RetryPolicy>,
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default)]
+    pub on_iter_error: IterErrorPolicy,
+    #[serde(default)]
+    pub precount: bool,
 }
 
 #[serde_as]
@@ -88,14 +238,28 @@ pub struct Setting {
     pub env: HashMap<String, String>,
     pub header: HashMap<String, String>,
     pub steps: HashMap<String, Step>,
+    #[serde(default)]
+    pub edges: Vec<(String, String)>,
+    pub rate_limit: Option<RateLimit>,
+    pub cookies: Option<CookieConfig>,
+    pub auth: Option<AuthConfig>,
+}
+
+/// How a step ended; only `Succeeded` lets dependent pipeline steps start.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepOutcome {
+    Succeeded,
+    TasksFailed(u32),
+    Stopped,
 }
 
 pub struct StepHandle {
     #[allow(dead_code)]
     pub name: String,
     pub semaphore: Arc<Semaphore>,
-    pub state: Arc<AtomicU8>,
-    pub control: Arc<(Mutex<()>, Condvar)>
+    pub state: watch::Sender<u8>,
+    pub cancel: Shared<CancellationToken>,
+    pub stats: Arc<StepCounters>,
 }
 
 
@@ -120,6 +284,56 @@ pub struct StepNotify {
     pub message: String,
 }
 
+#[skip_serializing_none]
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TaskPreview {
+    pub vals: ItemData,
+    pub method: Option<String>,
+    pub url: Option<String>,
+    pub header: Option<HashMap<String, String>>,
+    pub body: Option<String>,
+    pub save_path: Option<String>,
+    pub command: Option<String>,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StepPreview {
+    pub total: usize,
+    pub tasks: Vec<TaskPreview>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StepStats {
+    pub step: String,
+    pub total: Option<u32>,
+    /// `total` minus the tasks processed or running; None without a total
+    pub queued: Option<u32>,
+    pub running: u32,
+    pub succeeded: u32,
+    pub skipped: u32,
+    pub failed: u32,
+    pub elapsed_ms: u32,
+    pub throughput: f64,
+    pub eta_ms: Option<u32>,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+pub enum StepEvent {
+    StepWaiting { step: String, parents: Vec<String> },
+    StepStarted { step: String },
+    IterError { step: String, error: String },
+    TaskFinished { step: String, message: String },
+    TaskSkipped { step: String, message: String },
+    TaskRetry { step: String, message: String, attempt: u32, delay_ms: u32, error: String },
+    TaskFailed { step: String, message: String, error: String },
+    StepEnded { step: String },
+    StepStopped { step: String },
+    StepSkipped { step: String },
+    StepFailed { step: String, error: String },
+    Progress { stats: StepStats },
+}
+
 #[derive(Type, Serialize, Deserialize, Error, Debug)]
 pub enum ApiError {
     #[error("Scraping error: {0}")]
@@ -131,6 +345,15 @@ pub enum ApiError {
     #[error("reqwest error: {0}")]
     ReqwestError(String),
 
+    #[error("timeout error: {0}")]
+    Timeout(String),
+
+    #[error("connect error: {0}")]
+    Connect(String),
+
+    #[error("HTTP status {0}: {2}")]
+    HttpStatus(u16, Option<u64>, String),
+
     #[error("IO error: {0}")]
     Io(String),
 
@@ -142,6 +365,12 @@ pub enum ApiError {
 
     #[error("Glob error: {0}")]
     GlobError(String),
+
+    #[error("Iterator error: {0}")]
+    IterError(String),
+
+    #[error("Auth error: {0}")]
+    AuthError(String),
 }
 
 impl From<handlebars::TemplateError> for ApiError {
@@ -169,7 +398,13 @@ impl From<reqwest::header::InvalidHeaderValue> for ApiError {
 }
 impl From<reqwest::Error> for ApiError {
     fn from(e: reqwest::Error) -> Self {
-        ApiError::ReqwestError(e.to_string())
+        if e.is_timeout() {
+            ApiError::Timeout(e.to_string())
+        } else if e.is_connect() {
+            ApiError::Connect(e.to_string())
+        } else {
+            ApiError::ReqwestError(e.to_string())
+        }
     }
 }
 
@@ -185,6 +420,11 @@ impl From<std::num::ParseIntError> for ApiError {
     }
 }
 
+impl From<chrono::ParseError> for ApiError {
+    fn from(e: chrono::ParseError) -> Self {
+        ApiError::ParseError(e.to_string())
+    }
+}
 impl From<serde_json::error::Error> for ApiError {
     fn from(e: serde_json::error::Error) -> Self {
         ApiError::JsonError(e.to_string())
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use petgraph::algo::toposort;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+
+use crate::models::{ApiError, Result, Step};
+
+#[derive(Default)]
+pub struct StepGraph {
+    pub graph: DiGraph<String, ()>,
+    pub order: Vec<NodeIndex>,
+}
+
+impl StepGraph {
+    pub fn new(steps: &HashMap<String, Step>, edges: &[(String, String)]) -> Result<Self> {
+        let mut graph = DiGraph::<String, ()>::new();
+        let mut nodes = HashMap::<String, NodeIndex>::new();
+
+        let mut names: Vec<&String> = steps.keys().collect();
+        names.sort();
+        for nm in names {
+            nodes.insert(nm.clone(), graph.add_node(nm.clone()));
+        }
+
+        for (from, to) in edges.iter() {
+            let Some(&from_idx) = nodes.get(from) else {
+                return Err(ApiError::ScrapingError(format!("edge: step not found {}", from)));
+            };
+            let Some(&to_idx) = nodes.get(to) else {
+                return Err(ApiError::ScrapingError(format!("edge: step not found {}", to)));
+            };
+            graph.update_edge(from_idx, to_idx, ());
+        }
+
+        let order = toposort(&graph, None).map_err(|cycle| {
+            ApiError::ScrapingError(format!("edge: cycle at step {}", graph[cycle.node_id()]))
+        })?;
+
+        Ok(StepGraph { graph, order })
+    }
+
+    /// Steps in topological order, each with the steps it depends on.
+    pub fn schedule(&self) -> Vec<(String, Vec<String>)> {
+        self.order
+            .iter()
+            .map(|&idx| {
+                let parents = self
+                    .graph
+                    .neighbors_directed(idx, Direction::Incoming)
+                    .map(|p| self.graph[p].clone())
+                    .collect();
+                (self.graph[idx].clone(), parents)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::models::{ApiError, Result};
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct RateLimit {
+    pub requests_per_second: Option<f64>,
+    pub requests_per_minute: Option<f64>,
+    pub min_delay_ms: Option<u64>,
+    pub jitter_ms: Option<u64>,
+}
+
+impl RateLimit {
+    pub fn interval(&self) -> Duration {
+        let mut interval = Duration::ZERO;
+        if let Some(rps) = self.requests_per_second.filter(|v| *v > 0.0) {
+            interval = interval.max(Duration::from_secs_f64(1.0 / rps));
+        }
+        if let Some(rpm) = self.requests_per_minute.filter(|v| *v > 0.0) {
+            interval = interval.max(Duration::from_secs_f64(60.0 / rpm));
+        }
+        if let Some(min_delay_ms) = self.min_delay_ms {
+            interval = interval.max(Duration::from_millis(min_delay_ms));
+        }
+        interval
+    }
+}
+
+/// Next free request slot per host, shared by every step using the same client.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    pub next_slots: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        RateLimiter::default()
+    }
+
+    pub async fn acquire_url(&self, url: &str, rate_limit: &RateLimit) -> Result<()> {
+        let url = reqwest::Url::parse(url).map_err(|e| ApiError::ParseError(e.to_string()))?;
+        self.acquire(url.host_str().unwrap_or_default(), rate_limit).await;
+        Ok(())
+    }
+
+    pub async fn acquire(&self, host: &str, rate_limit: &RateLimit) {
+        let interval = rate_limit.interval();
+        let jitter = match rate_limit.jitter_ms {
+            Some(jitter_ms) if jitter_ms > 0 => Duration::from_millis(rand::random_range(0..=jitter_ms)),
+            _ => Duration::ZERO,
+        };
+        if interval.is_zero() && jitter.is_zero() {
+            return;
+        }
+
+        let slot = {
+            let mut next_slots = self.next_slots.lock().await;
+            let now = Instant::now();
+            let next = next_slots.get(host).copied().unwrap_or(now);
+            let slot = next.max(now) + jitter;
+            next_slots.insert(host.to_string(), slot + interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
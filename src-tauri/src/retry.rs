@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::models::ApiError;
+
+/// Upper bound for a server's `Retry-After` when the policy has no `max_delay_ms`.
+const MAX_RETRY_AFTER_MS: u64 = 300_000;
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub enum RetryErrorKind {
+    Timeout,
+    Connect,
+    Reqwest,
+    Io,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: Option<u64>,
+    pub jitter_ms: Option<u64>,
+    #[serde(default = "default_retry_statuses")]
+    pub retry_statuses: Vec<u16>,
+    #[serde(default = "default_retry_errors")]
+    pub retry_errors: Vec<RetryErrorKind>,
+}
+
+fn default_retry_statuses() -> Vec<u16> {
+    vec![429, 500, 502, 503, 504]
+}
+
+fn default_retry_errors() -> Vec<RetryErrorKind> {
+    vec![RetryErrorKind::Timeout, RetryErrorKind::Connect]
+}
+
+impl RetryPolicy {
+    pub fn is_retryable(&self, e: &ApiError) -> bool {
+        let kind = match e {
+            ApiError::HttpStatus(status, _, _) => return self.retry_statuses.contains(status),
+            ApiError::Timeout(_) => RetryErrorKind::Timeout,
+            ApiError::Connect(_) => RetryErrorKind::Connect,
+            ApiError::ReqwestError(_) => RetryErrorKind::Reqwest,
+            ApiError::Io(_) => RetryErrorKind::Io,
+            _ => return false,
+        };
+        self.retry_errors.contains(&kind)
+    }
+
+    /// Delay before the next attempt; `attempt` is the 1-based attempt that just failed.
+    pub fn delay(&self, attempt: u32, e: &ApiError) -> Duration {
+        if let ApiError::HttpStatus(429 | 503, Some(retry_after), _) = e {
+            let max_delay_ms = self.max_delay_ms.unwrap_or(MAX_RETRY_AFTER_MS);
+            return Duration::from_millis(retry_after.saturating_mul(1000).min(max_delay_ms));
+        }
+        let exp = attempt.saturating_sub(1).min(16);
+        let mut delay_ms = self.base_delay_ms.saturating_mul(1 << exp);
+        if let Some(max_delay_ms) = self.max_delay_ms {
+            delay_ms = delay_ms.min(max_delay_ms);
+        }
+        if let Some(jitter_ms) = self.jitter_ms {
+            delay_ms += rand::random_range(0..=jitter_ms);
+        }
+        Duration::from_millis(delay_ms)
+    }
+}
+
+/// `Retry-After` is either delta-seconds or an HTTP-date.
+pub fn parse_retry_after(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (date.with_timezone(&Utc) - Utc::now()).num_seconds();
+    Some(secs.max(0) as u64)
+}
@@ -1,8 +1,7 @@
 use std::collections::{HashMap};
 use std::path::{absolute, PathBuf};
 use std::pin::Pin;
-use std::sync::atomic::{AtomicU8, Ordering};
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::Arc;
 
 use async_stream::stream;
 use chardetng::EncodingDetector;
@@ -12,40 +11,72 @@ use reqwest::{Client};
 use tokio::io::{AsyncReadExt};
 use tokio::sync::Semaphore;
 use tokio::sync::{RwLock};
+use tokio::sync::watch;
 use tokio_stream::{Stream, StreamExt};
-use tauri::Emitter;
-
-use crate::tasks::task::{Task};
+use tokio_util::sync::CancellationToken;
+
+use crate::sinks::event_sink::SharedSink;
+use crate::pipeline::StepGraph;
+use crate::ledger::{task_key, Ledger};
+use crate::retry::RetryPolicy;
+use crate::tasks::task::{Job, Task, TaskContext};
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::cookies::CookieJar;
+use crate::auth::AuthSession;
+use crate::stats::StepCounters;
+use crate::utils::{get_handlebars, uses_var};
 use crate::models::{Result, ApiError, IterRange,
-                    Setting, Step, StepHandle, TaskIter,
-                    TextContent, StepNotify,
+                    Setting, Step, StepHandle, StepOutcome, TaskIter, IterErrorPolicy, IterMode,
+                    TextContent, StepEvent, StepPreview, StepStats,
                     Shared, ItemData,
                     STEP_RUNNING, STEP_STOPPED, STEP_PAUSED
 };
 
-use crate::iters::vec_iter::get_iter_vec;
-use crate::iters::range_iter::get_iter_range;
-use crate::iters::pattern_iter::get_iter_pattern;
-use crate::iters::range_pattern_iter::get_iter_range_pattern;
-use crate::iters::glob_json_range_pattern_iter::get_iter_glob_json_range_pattern;
-use crate::iters::glob_json_pattern_iter::get_iter_glob_json_pattern;
+use crate::iters::{get_iter, IterContext, ItemStream, SeenSet};
 
+#[derive(Clone)]
 pub struct Scraping {
     pub client: Client,
     pub env: Shared<HashMap<String, String>>,
     pub header: Shared<HashMap<String, String>>,
     pub steps: Shared<HashMap<String, Step>>,
     pub step_handles: Shared<HashMap<String, StepHandle>>,
+    pub step_graph: Shared<StepGraph>,
+    pub rate_limit: Shared<Option<RateLimit>>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub cookie_jar: Arc<CookieJar>,
+    pub cookie_file: Shared<Option<String>>,
+    pub auth: Shared<Option<Arc<AuthSession>>>,
+    /// Some while a pipeline runs
+    pub pipeline_cancel: Shared<Option<CancellationToken>>,
+}
+
+impl Default for Scraping {
+    fn default() -> Self {
+        Scraping::new()
+    }
 }
 
 impl Scraping {
     pub fn new() -> Self {
+        let cookie_jar = Arc::new(CookieJar::new());
+        let client = Client::builder()
+            .cookie_provider(cookie_jar.clone())
+            .build()
+            .unwrap_or_default();
         Scraping {
-            client: Client::new(),
+            client,
             env: Arc::new(RwLock::new(HashMap::new())),
             header: Arc::new(RwLock::new(HashMap::new())),
             steps: Arc::new(RwLock::new(HashMap::new())),
             step_handles: Arc::new(RwLock::new(HashMap::new())),
+            step_graph: Arc::new(RwLock::new(StepGraph::default())),
+            rate_limit: Arc::new(RwLock::new(None)),
+            rate_limiter: Arc::new(RateLimiter::new()),
+            cookie_jar,
+            cookie_file: Arc::new(RwLock::new(None)),
+            auth: Arc::new(RwLock::new(None)),
+            pipeline_cancel: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -61,7 +92,7 @@ impl Scraping {
             match absolute(&args[1]) {
                 Ok(path) => Some(path.to_string_lossy().to_string()),
                 Err(e) => {
-                    println!("{:?}", e);
+                    eprintln!("{:?}", e);
                     None
                 }
             }
@@ -92,7 +123,7 @@ impl Scraping {
         //     }
         // }
 
-        println!("mime_type: {}", mime_type);
+        eprintln!("mime_type: {}", mime_type);
 
         let sz = path.metadata()?.len();
 
@@ -130,24 +161,34 @@ impl Scraping {
     }
 
     pub async fn load(&mut self, setting: Setting) -> Result<()> {
-        println!("setting: {:?}", setting);
+        eprintln!("setting: {:?}", setting);
+        let step_graph = StepGraph::new(&setting.steps, &setting.edges)?;
         let mut step_handles = HashMap::<String, StepHandle>::new();
         for (nm, step) in setting.steps.iter() {
             let concurrency_limit = step.concurrency_limit;
             let step_handle = StepHandle {
                 name: nm.clone(),
                 semaphore: Arc::new(Semaphore::new(concurrency_limit)),
-                // stop: Arc::new(AtomicBool::new(false)),
-                state: Arc::new(AtomicU8::new(STEP_RUNNING)),
-                control: Arc::new((Mutex::new(()), Condvar::new()))
+                state: watch::channel(STEP_RUNNING).0,
+                cancel: Arc::new(RwLock::new(CancellationToken::new())),
+                stats: Arc::new(StepCounters::default()),
             };
             step_handles.insert(nm.clone(), step_handle);
         }
 
+        let cookie_file = self.cookie_jar.load(&setting.cookies, &setting.env)?;
+
         self.assign(&self.env, setting.env).await;
         self.assign(&self.header, setting.header).await;
         self.assign(&self.steps, setting.steps).await;
         self.assign(&self.step_handles, step_handles).await;
+        self.assign(&self.step_graph, step_graph).await;
+        self.assign(&self.rate_limit, setting.rate_limit).await;
+        self.assign(&self.cookie_file, cookie_file).await;
+        let auth = setting
+            .auth
+            .map(|auth| Arc::new(AuthSession::new(auth, self.client.clone(), self.env.clone())));
+        self.assign(&self.auth, auth).await;
 
         Ok(())
     }
@@ -157,127 +198,237 @@ impl Scraping {
         let step_handle = step_handles
             .get(&step_name)
             .ok_or(ApiError::ScrapingError("Step not found".to_string()))?;
-        let state = step_handle.state.clone();
-        state.store(val, Ordering::SeqCst);
-        let control = step_handle.control.clone();
-        let (_, cvar) = &*control;
-        cvar.notify_all();
+        step_handle.state.send_replace(val);
+        if val == STEP_STOPPED {
+            step_handle.cancel.read().await.cancel();
+        }
         Ok(())
     }
 
-    pub async fn run_step(&self, step_name: String, window: tauri::Window) -> Result<()> {
-        println!("Start Step: {}", &step_name);
-        let notify = StepNotify {
-            name: "status".to_string(),
-            status: "start".to_string(),
-            message: format!("Start Step {}", &step_name)
+    pub async fn get_step_stats(&self, step_name: String) -> Result<StepStats> {
+        let step_handles = self.step_handles.read().await;
+        let step_handle = step_handles
+            .get(&step_name)
+            .ok_or(ApiError::ScrapingError("Step not found".to_string()))?;
+        Ok(step_handle.stats.snapshot(&step_name))
+    }
+
+    async fn task_context(&self, step: &Step) -> TaskContext {
+        let rate_limit = match &step.rate_limit {
+            Some(rate_limit) => Some(rate_limit.clone()),
+            None => self.rate_limit.read().await.clone(),
         };
-        let window_clone = window.clone();
-        window_clone.emit("status", notify).unwrap();
+        TaskContext {
+            client: self.client.clone(),
+            header: self.header.read().await.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            rate_limit,
+            auth: self.auth.read().await.clone(),
+            cookie_jar: self.cookie_jar.clone(),
+        }
+    }
+
+    pub async fn preview_step(&self, step_name: String, limit: usize) -> Result<StepPreview> {
+        let steps = self.steps.read().await;
+        let step = steps
+            .get(&step_name)
+            .ok_or(ApiError::ScrapingError("Step not found".to_string()))?;
+        let mut job = step.job.clone();
+        let env = self.env.read().await.clone();
+        let ctx = self.task_context(step).await;
+        let task_iters = step_task_iters(step, &step_name);
+        let iter_mode = step.iter_mode.clone();
+
+        let mut preview = StepPreview::default();
+        if let Err(e) = job.pre_process() {
+            preview.errors.push(format!("{:?}", e));
+        }
+
+        let mut stream = get_iters(task_iters, iter_mode, env, None);
+        while let Some(item) = stream.next().await {
+            let (vals, cur_env) = match item {
+                Ok(item) => item,
+                Err(e) => {
+                    if preview.errors.len() < limit {
+                        preview.errors.push(format!("{:?}", e));
+                    }
+                    continue;
+                }
+            };
+            preview.total += 1;
+            if preview.tasks.len() >= limit && preview.errors.len() >= limit {
+                continue;
+            }
+            match job.make_task(cur_env, &ctx).await {
+                Ok(task) => {
+                    if preview.tasks.len() < limit {
+                        let mut task_preview = task.preview();
+                        task_preview.vals = vals.into_iter().flatten().flatten().collect();
+                        preview.tasks.push(task_preview);
+                    }
+                }
+                Err(e) => {
+                    if preview.errors.len() < limit {
+                        preview.errors.push(format!("{:?}", e));
+                    }
+                }
+            }
+        }
+        Ok(preview)
+    }
+
+    pub async fn run_step(&self, step_name: String, sink: SharedSink) -> Result<StepOutcome> {
+        self.run_step_in(step_name, sink, &CancellationToken::new()).await
+    }
+
+    /// Runs a step whose cancel token is a child of `parent`, so stopping the pipeline stops it too.
+    /// Every run ends with StepEnded, StepStopped or StepFailed.
+    async fn run_step_in(&self, step_name: String, sink: SharedSink, parent: &CancellationToken) -> Result<StepOutcome> {
+        sink.emit(StepEvent::StepStarted { step: step_name.clone() });
+        let res = self.run_step_tasks(step_name.clone(), sink.clone(), parent).await;
+        if let Err(e) = &res {
+            eprintln!("Error: {:?}", e);
+            sink.emit(StepEvent::StepFailed { step: step_name, error: format!("{:?}", e) });
+        }
+        res
+    }
+
+    async fn run_step_tasks(&self, step_name: String, sink: SharedSink, parent: &CancellationToken) -> Result<StepOutcome> {
+        eprintln!("Start Step: {}", &step_name);
 
         let steps = self.steps.read().await;
         let step = steps
             .get(&step_name)
             .ok_or(ApiError::ScrapingError("Step not found".to_string()))?;
         let mut job = step.job.clone();
-        let env_lock = self.env.read().await;
-        let env = env_lock.clone();
-        let header_lock = self.header.read().await;
-        let g_header = header_lock.clone();
-
-        let mut task_iters = step.task_iters.clone();
-        if task_iters.is_empty() {
-            task_iters.push(TaskIter::Range(IterRange {
-                name: format!("IDX_{}", &step_name),
-                offset: "0".to_string(),
-                take: "1".to_string(),
-            }))
+        let ctx = self.task_context(step).await;
+        if let Some(auth) = &ctx.auth {
+            if step_needs_auth(step, &auth.token_name()) {
+                auth.token().await?;
+            }
         }
+        let env = self.env.read().await.clone();
+        let task_iters = step_task_iters(step, &step_name);
+        let iter_mode = step.iter_mode.clone();
         job.pre_process()?;
+        let retry = step.retry.clone();
+        let on_iter_error = step.on_iter_error.clone();
+        let total = if step.precount {
+            Some(count_iters(task_iters.clone(), iter_mode.clone(), env.clone(), Some(ctx.clone())).await)
+        } else {
+            None
+        };
+
+        let ledger = match &step.ledger {
+            Some(ledger_path) => Some(Arc::new(Ledger::open(&get_handlebars(ledger_path, &env)?)?)),
+            None => None,
+        };
 
         let step_handles = self.step_handles.read().await;
         let step_handle = step_handles
             .get(&step_name)
             .ok_or(ApiError::ScrapingError("Step not found".to_string()))?;
         let semaphore = step_handle.semaphore.clone();
+        let stats = step_handle.stats.clone();
+        stats.reset(total);
+        sink.emit(StepEvent::Progress { stats: stats.snapshot(&step_name) });
 
-        let state = step_handle.state.clone();
-        state.store(STEP_RUNNING, Ordering::SeqCst);
-        let control = step_handle.control.clone();
+        step_handle.state.send_replace(STEP_RUNNING);
+        let mut state_rx = step_handle.state.subscribe();
+        let cancel = parent.child_token();
+        *step_handle.cancel.write().await = cancel.clone();
 
         let mut handles = Vec::new();
 
-        let mut stream = get_iters(task_iters, env.clone());
-        while let Some((vals, cur_env)) = stream.next().await {
-            println!("iter: {:?}", vals);
-            let semaphore = semaphore.clone();
-            let Ok(permit) = semaphore.acquire_owned().await else { return Err(ApiError::ScrapingError("err semaphore.acquire_owned".to_string())); };
-
-            match state.load(Ordering::SeqCst) {
-                STEP_RUNNING => {
-                    println!("STEP_RUNNING");
-                }
-                STEP_PAUSED => {
-                    println!("STEP_PAUSED");
-                    let (lock, cvar) = &*control;
-                    let _guard = cvar
-                        .wait_while(lock.lock().unwrap(), |_| {
-                            state.load(Ordering::SeqCst) == STEP_PAUSED
-                        })
-                        .unwrap();
+        let mut iter_err = None;
+        let mut stream = get_iters(task_iters, iter_mode, env.clone(), Some(ctx.clone()));
+        while let Some(item) = stream.next().await {
+            let (vals, cur_env) = match item {
+                Ok(item) => item,
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    sink.emit(StepEvent::IterError { step: step_name.clone(), error: format!("{:?}", e) });
+                    if on_iter_error == IterErrorPolicy::Abort {
+                        cancel.cancel();
+                        iter_err = Some(e);
+                        break;
+                    }
+                    continue;
                 }
-                STEP_STOPPED => {
-                    println!("STEP_STOPPED");
-                    break;
+            };
+            eprintln!("iter: {:?}", vals);
+            let key = task_key(&vals);
+            if let Some(ledger) = &ledger {
+                if ledger.is_done(&key) {
+                    sink.emit(StepEvent::TaskSkipped { step: step_name.clone(), message: key });
+                    StepCounters::incr(&stats.skipped);
+                    sink.emit(StepEvent::Progress { stats: stats.snapshot(&step_name) });
+                    continue;
                 }
-                _ => {}
             }
+            let semaphore = semaphore.clone();
+            let Ok(permit) = semaphore.acquire_owned().await else {
+                cancel.cancel();
+                iter_err = Some(ApiError::ScrapingError("err semaphore.acquire_owned".to_string()));
+                break;
+            };
 
-            let window_clone = window.clone();
-            let task = job.make_task(cur_env, self.client.clone(), g_header.clone()).await?;
-            let handle = tokio::task::spawn(async move {
-                if let Err(e) = task.clone().run_task().await {
-                    eprintln!("Error: {:?}", e);
-                    let notify = StepNotify {
-                        name: "error".to_string(),
-                        status: "".to_string(),
-                        message: format!("{:?}", e)
-                    };
-                    let window_clone = window_clone.clone();
-                    window_clone.emit("error", notify).unwrap();
+            if *state_rx.borrow_and_update() == STEP_PAUSED {
+                eprintln!("STEP_PAUSED");
+                tokio::select! {
+                    _ = state_rx.wait_for(|state| *state != STEP_PAUSED) => {}
+                    _ = cancel.cancelled() => {}
                 }
+            }
+            if cancel.is_cancelled() {
+                eprintln!("STEP_STOPPED");
+                break;
+            }
 
-                let task_notify = match task.clone() {
-                    Task::HttpTask(http_task) => {
-                        StepNotify {
-                            name: "progress".to_string(),
-                            status: "".to_string(),
-                            message: http_task.save_path.clone()
-                        }
+            let sink = sink.clone();
+            let stats = stats.clone();
+            let ledger = ledger.clone();
+            let cancel = cancel.clone();
+            let retry = retry.clone();
+            let task_step_name = step_name.clone();
+            let task = match job.make_task(cur_env, &ctx).await {
+                Ok(task) => task,
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    StepCounters::incr(&stats.failed);
+                    sink.emit(StepEvent::TaskFailed { step: step_name.clone(), message: key, error: format!("{:?}", e) });
+                    sink.emit(StepEvent::Progress { stats: stats.snapshot(&step_name) });
+                    if on_iter_error == IterErrorPolicy::Abort {
+                        cancel.cancel();
+                        iter_err = Some(e);
+                        break;
                     }
-                    Task::HtmlTask(html_task) => {
-                        StepNotify {
-                            name: "progress".to_string(),
-                            status: "".to_string(),
-                            message: html_task.save_path.clone()
-                        }
+                    continue;
+                }
+            };
+            StepCounters::incr(&stats.running);
+            let handle = tokio::task::spawn(async move {
+                let message = task.describe();
+                let res = run_task_with_retry(task, retry, cancel, &sink, &task_step_name, &message).await;
+                if let Some(ledger) = &ledger {
+                    let error = res.as_ref().err().map(|e| format!("{:?}", e));
+                    if let Err(e) = ledger.record(key, error) {
+                        eprintln!("Error: {:?}", e);
                     }
-                    Task::CsvTask(csv_task) => {
-                        StepNotify {
-                            name: "progress".to_string(),
-                            status: "".to_string(),
-                            message: csv_task.save_path.clone()
-                        }
+                }
+                StepCounters::decr(&stats.running);
+                match res {
+                    Ok(_) => {
+                        StepCounters::incr(&stats.succeeded);
+                        sink.emit(StepEvent::TaskFinished { step: task_step_name.clone(), message });
                     }
-                    Task::ShellTask(shell_task) => {
-                        StepNotify {
-                            name: "progress".to_string(),
-                            status: "".to_string(),
-                            message: format!("{} {:?}", shell_task.shell, shell_task.args)
-                        }
+                    Err(e) => {
+                        eprintln!("Error: {:?}", e);
+                        StepCounters::incr(&stats.failed);
+                        sink.emit(StepEvent::TaskFailed { step: task_step_name.clone(), message, error: format!("{:?}", e) });
                     }
-                };
-                window_clone.emit(&task_notify.name.clone(), task_notify.clone()).unwrap();
+                }
+                sink.emit(StepEvent::Progress { stats: stats.snapshot(&task_step_name) });
                 drop(permit);
             });
             handles.push(handle);
@@ -289,59 +440,245 @@ impl Scraping {
                 Err(e) => eprintln!("Error: {:?}", e),
             };
         }
+        if let Some(cookie_file) = self.cookie_file.read().await.as_ref() {
+            if let Err(e) = self.cookie_jar.save(cookie_file) {
+                eprintln!("Error: {:?}", e);
+            }
+        }
+        if let Some(e) = iter_err {
+            return Err(e);
+        }
+        if cancel.is_cancelled() {
+            sink.emit(StepEvent::StepStopped { step: step_name.clone() });
+            eprintln!("Stop Step: {}", &step_name);
+            return Ok(StepOutcome::Stopped);
+        }
+        let failed = stats.failed.load(std::sync::atomic::Ordering::SeqCst);
+        if failed > 0 {
+            sink.emit(StepEvent::StepFailed { step: step_name.clone(), error: format!("{} tasks failed", failed) });
+            eprintln!("End Step: {} ({} tasks failed)", &step_name, failed);
+            return Ok(StepOutcome::TasksFailed(failed));
+        }
 
-        let notify = StepNotify {
-            name: "status".to_string(),
-            status: "end".to_string(),
-            message: format!("End Step {}", &step_name)
-        };
-        let window_clone = window.clone();
-        window_clone.emit("status", notify).unwrap();
+        sink.emit(StepEvent::StepEnded { step: step_name.clone() });
+
+        eprintln!("End Step: {}", &step_name);
+        Ok(StepOutcome::Succeeded)
+    }
+
+    /// Stops every running step of the current pipeline; steps that have not started are skipped.
+    pub async fn stop_pipeline(&self) {
+        if let Some(cancel) = self.pipeline_cancel.read().await.as_ref() {
+            cancel.cancel();
+        }
+    }
+
+    /// Runs every step in dependency order; fails, naming each step that did not succeed,
+    /// when any step failed, was stopped or was skipped. Only one pipeline runs at a time.
+    pub async fn run_pipeline(&self, sink: SharedSink) -> Result<()> {
+        let cancel = CancellationToken::new();
+        {
+            let mut pipeline_cancel = self.pipeline_cancel.write().await;
+            if pipeline_cancel.is_some() {
+                return Err(ApiError::ScrapingError("a pipeline is already running".to_string()));
+            }
+            *pipeline_cancel = Some(cancel.clone());
+        }
+        eprintln!("Start Pipeline");
+        let schedule = self.step_graph.read().await.schedule();
+
+        let mut done_rxs = HashMap::<String, watch::Receiver<Option<bool>>>::new();
+        let mut handles = Vec::new();
+        for (step_name, parents) in schedule {
+            let (done_tx, done_rx) = watch::channel::<Option<bool>>(None);
+            let parent_rxs: Vec<watch::Receiver<Option<bool>>> = parents
+                .iter()
+                .filter_map(|p| done_rxs.get(p).cloned())
+                .collect();
+            done_rxs.insert(step_name.clone(), done_rx);
+
+            let scraping = self.clone();
+            let sink = sink.clone();
+            let cancel = cancel.clone();
+            // None when the step succeeded, otherwise why it did not
+            let handle = tokio::task::spawn(async move {
+                sink.emit(StepEvent::StepWaiting { step: step_name.clone(), parents });
+
+                for mut parent_rx in parent_rxs {
+                    let parent_ok = tokio::select! {
+                        done = parent_rx.wait_for(|done| done.is_some()) => {
+                            done.map(|done| *done == Some(true)).unwrap_or(false)
+                        }
+                        _ = cancel.cancelled() => false,
+                    };
+                    if !parent_ok {
+                        sink.emit(StepEvent::StepSkipped { step: step_name.clone() });
+                        let _ = done_tx.send(Some(false));
+                        return Some(format!("{} skipped", step_name));
+                    }
+                }
+                if cancel.is_cancelled() {
+                    sink.emit(StepEvent::StepSkipped { step: step_name.clone() });
+                    let _ = done_tx.send(Some(false));
+                    return Some(format!("{} skipped", step_name));
+                }
+
+                let failure = match scraping.run_step_in(step_name.clone(), sink.clone(), &cancel).await {
+                    Ok(StepOutcome::Succeeded) => None,
+                    Ok(StepOutcome::TasksFailed(failed)) => Some(format!("{} had {} failed tasks", step_name, failed)),
+                    Ok(StepOutcome::Stopped) => Some(format!("{} stopped", step_name)),
+                    Err(e) => Some(format!("{} failed: {}", step_name, e)),
+                };
+                let _ = done_tx.send(Some(failure.is_none()));
+                failure
+            });
+            handles.push(handle);
+        }
 
-        println!("End Step: {}", &step_name);
+        let mut failures = Vec::new();
+        for handle in handles {
+            match handle.await {
+                Ok(failure) => failures.extend(failure),
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    failures.push(format!("{:?}", e));
+                }
+            };
+        }
+        *self.pipeline_cancel.write().await = None;
+        eprintln!("End Pipeline");
+        if !failures.is_empty() {
+            return Err(ApiError::ScrapingError(format!("pipeline: {}", failures.join(", "))));
+        }
         Ok(())
     }
 
 }
 
 
+/// HttpJob steps and steps whose templates use the token log in before their first task;
+/// remote sitemaps fetched by other steps log in when they are read.
+fn step_needs_auth(step: &Step, token_name: &str) -> bool {
+    matches!(step.job, Job::HttpJob(_))
+        || serde_json::to_string(step).is_ok_and(|json| uses_var(&json, token_name))
+}
+
+fn step_task_iters(step: &Step, step_name: &str) -> Vec<TaskIter> {
+    let mut task_iters = step.task_iters.clone();
+    if task_iters.is_empty() {
+        task_iters.push(TaskIter::Range(IterRange {
+            name: format!("IDX_{}", step_name),
+            offset: "0".to_string(),
+            take: Some("1".to_string()),
+            end: None,
+            step: None,
+            inclusive: None,
+        }))
+    }
+    task_iters
+}
+
+async fn count_iters(task_iters: Vec<TaskIter>, iter_mode: IterMode, env: HashMap<String, String>, http: Option<TaskContext>) -> u32 {
+    let mut total = 0;
+    let mut stream = get_iters(task_iters, iter_mode, env, http);
+    while let Some(item) = stream.next().await {
+        if item.is_ok() {
+            total += 1;
+        }
+    }
+    total
+}
+
+async fn run_task_with_retry(
+    mut task: Task,
+    retry: Option<RetryPolicy>,
+    cancel: CancellationToken,
+    sink: &SharedSink,
+    step_name: &str,
+    message: &str,
+) -> Result<()> {
+    let mut attempt = 1;
+    loop {
+        let res = tokio::select! {
+            res = task.run_task() => res,
+            _ = cancel.cancelled() => return Err(ApiError::ScrapingError("stopped".to_string())),
+        };
+        let Err(e) = res else { return Ok(()); };
+        let Some(policy) = retry.as_ref() else { return Err(e); };
+        if attempt >= policy.max_attempts || !policy.is_retryable(&e) {
+            return Err(e);
+        }
+
+        let delay = policy.delay(attempt, &e);
+        sink.emit(StepEvent::TaskRetry {
+            step: step_name.to_string(),
+            message: message.to_string(),
+            attempt,
+            delay_ms: delay.as_millis() as u32,
+            error: format!("{:?}", e),
+        });
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = cancel.cancelled() => return Err(ApiError::ScrapingError("stopped".to_string())),
+        }
+        attempt += 1;
+    }
+}
+
+type StepIterStream = Pin<Box<dyn Stream<Item = Result<(Vec<Option<ItemData>>, HashMap<String, String>)>> + Send>>;
+
+/// `http` is None in preview, so iterators do not fetch anything there.
+fn get_iters(task_iters: Vec<TaskIter>, iter_mode: IterMode, env: HashMap<String, String>, http: Option<TaskContext>) -> StepIterStream {
+    match iter_mode {
+        IterMode::Product => get_iters_product(task_iters, env, http),
+        IterMode::Zip => get_iters_zip(task_iters, env, http, false),
+        IterMode::ZipLongest => get_iters_zip(task_iters, env, http, true),
+        IterMode::Chain => get_iters_chain(task_iters, env, http),
+    }
+}
 
-fn get_iters(
-    task_iters: Vec<TaskIter>,
-    env: HashMap<String, String>,
-) -> Pin<Box<dyn Stream<Item = (Vec<Option<ItemData>>, HashMap<String, String>)> + Send>> {
+fn get_iters_product(task_iters: Vec<TaskIter>, env: HashMap<String, String>, http: Option<TaskContext>) -> StepIterStream {
     Box::pin(stream! {
         let mut cur_vals: Vec<Option<ItemData>> = Vec::new();
-        let mut iters: Vec<Pin<Box<dyn Stream<Item = ItemData> + Send>>> = Vec::new();
+        let mut iters: Vec<ItemStream> = Vec::new();
+        let mut need_iters: Vec<bool> = Vec::new();
+        let mut seens: Vec<SeenSet> = Vec::new();
         let mut env = env.clone();
         let len = task_iters.len();
         
         for _i in 0..len {
             iters.push(Box::pin(tokio_stream::empty()));
             cur_vals.push(None);
+            need_iters.push(true);
+            seens.push(SeenSet::default());
         }
         
         let mut pos = 0;
-        iters[pos] = get_iter(task_iters[pos].clone(), env.clone());
-        println!("Start iter loop");
+        eprintln!("Start iter loop");
         loop {
-            if pos != 0 && cur_vals[pos].is_none() {
-                iters[pos] = get_iter(task_iters[pos].clone(), env.clone());
+            if need_iters[pos] {
+                let ctx = IterContext { env: env.clone(), seen: seens[pos].clone(), http: http.clone() };
+                iters[pos] = get_iter(&task_iters[pos], ctx);
+                need_iters[pos] = false;
             }
-            let pos_val = iters[pos].next().await;
-            cur_vals[pos] = pos_val.clone();
-            match pos_val {
-                Some(pos_v) => {
-                    env.extend(pos_v.clone());
+            match iters[pos].next().await {
+                Some(Ok(pos_v)) => {
+                    cur_vals[pos] = Some(pos_v.clone());
+                    env.extend(pos_v);
                     if pos == len - 1 {
-                        yield (cur_vals.clone(), env.clone());
+                        yield Ok((cur_vals.clone(), env.clone()));
                     } else {
                         pos += 1;
+                        need_iters[pos] = true;
                     }
                 }
+                Some(Err(e)) => {
+                    yield Err(e);
+                }
                 None => {
+                    cur_vals[pos] = None;
                     if pos == 0 {
-                        println!("End iter loop");
+                        eprintln!("End iter loop");
                         break;
                     } else {
                         pos -= 1;
@@ -352,21 +689,168 @@ fn get_iters(
     })
 }
 
-
-fn get_iter(task_iter: TaskIter, env: HashMap<String, String>) -> Pin<Box<dyn Stream<Item = ItemData> + Send>> {
-    match task_iter {
-        TaskIter::Vec(iter_vec) => get_iter_vec(iter_vec),
-        TaskIter::Range(iter_range) => get_iter_range(iter_range, env),
-        TaskIter::Pattern(iter_pattern) => get_iter_pattern(iter_pattern),
-        TaskIter::RangePattern(iter_range_pattern) => {
-            get_iter_range_pattern(iter_range_pattern, env)
+fn get_iters_zip(task_iters: Vec<TaskIter>, env: HashMap<String, String>, http: Option<TaskContext>, longest: bool) -> StepIterStream {
+    Box::pin(stream! {
+        let mut iters: Vec<ItemStream> = task_iters
+            .into_iter()
+            .map(|task_iter| get_iter(&task_iter, IterContext { env: env.clone(), seen: SeenSet::default(), http: http.clone() }))
+            .collect();
+        let mut finished = vec![false; iters.len()];
+
+        eprintln!("Start iter loop");
+        'zip: loop {
+            let mut cur_vals: Vec<Option<ItemData>> = Vec::new();
+            let mut cur_env = env.clone();
+            for (pos, iter) in iters.iter_mut().enumerate() {
+                let mut val = None;
+                while !finished[pos] {
+                    match iter.next().await {
+                        Some(Ok(pos_v)) => {
+                            val = Some(pos_v);
+                            break;
+                        }
+                        Some(Err(e)) => yield Err(e),
+                        None => {
+                            finished[pos] = true;
+                            if !longest {
+                                break 'zip;
+                            }
+                        }
+                    }
+                }
+                if let Some(pos_v) = &val {
+                    cur_env.extend(pos_v.clone());
+                }
+                cur_vals.push(val);
+            }
+            if finished.iter().all(|f| *f) {
+                break;
+            }
+            yield Ok((cur_vals, cur_env));
         }
-        TaskIter::GlobJsonPattern(iter_glob_json_pattern) => {
-            get_iter_glob_json_pattern(iter_glob_json_pattern, env)
+        eprintln!("End iter loop");
+    })
+}
+
+fn get_iters_chain(task_iters: Vec<TaskIter>, env: HashMap<String, String>, http: Option<TaskContext>) -> StepIterStream {
+    Box::pin(stream! {
+        let len = task_iters.len();
+        eprintln!("Start iter loop");
+        for (pos, task_iter) in task_iters.into_iter().enumerate() {
+            let mut iter = get_iter(&task_iter, IterContext { env: env.clone(), seen: SeenSet::default(), http: http.clone() });
+            while let Some(item) = iter.next().await {
+                match item {
+                    Ok(pos_v) => {
+                        let mut cur_vals: Vec<Option<ItemData>> = vec![None; len];
+                        let mut cur_env = env.clone();
+                        cur_env.extend(pos_v.clone());
+                        cur_vals[pos] = Some(pos_v);
+                        yield Ok((cur_vals, cur_env));
+                    }
+                    Err(e) => yield Err(e),
+                }
+            }
         }
-        TaskIter::GlobJsonRangePattern(iter_glob_json_range_pattern) => {
-            get_iter_glob_json_range_pattern(iter_glob_json_range_pattern, env)
+        eprintln!("End iter loop");
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::memory_sink::MemorySink;
+
+    fn event_kind(event: &StepEvent) -> &'static str {
+        match event {
+            StepEvent::StepWaiting { .. } => "StepWaiting",
+            StepEvent::StepStarted { .. } => "StepStarted",
+            StepEvent::IterError { .. } => "IterError",
+            StepEvent::TaskFinished { .. } => "TaskFinished",
+            StepEvent::TaskSkipped { .. } => "TaskSkipped",
+            StepEvent::TaskRetry { .. } => "TaskRetry",
+            StepEvent::TaskFailed { .. } => "TaskFailed",
+            StepEvent::StepEnded { .. } => "StepEnded",
+            StepEvent::StepStopped { .. } => "StepStopped",
+            StepEvent::StepSkipped { .. } => "StepSkipped",
+            StepEvent::StepFailed { .. } => "StepFailed",
+            StepEvent::Progress { .. } => "Progress",
         }
     }
-}
 
+    #[tokio::test]
+    async fn run_step_emits_events_in_order() {
+        // CsvJob runs in-process, unlike a ShellJob `echo`, which is a cmd builtin on Windows
+        let output = std::env::temp_dir().join(format!("tr-scraping-run-step-{}", std::process::id()));
+        let setting: Setting = serde_json::from_value(serde_json::json!({
+            "env": {},
+            "header": {},
+            "steps": {
+                "csv": {
+                    "name": "csv",
+                    "task_iters": [{ "Vec": { "name": "K", "val": ["a", "b"] } }],
+                    "job": { "CsvJob": { "keys": ["K"], "sep": ",", "filename": "out.csv", "output": output.to_string_lossy() } },
+                    "concurrency_limit": 1
+                }
+            }
+        }))
+        .unwrap();
+        let mut scraping = Scraping::new();
+        scraping.load(setting).await.unwrap();
+
+        let sink = Arc::new(MemorySink::new());
+        let outcome = scraping.run_step("csv".to_string(), sink.clone()).await.unwrap();
+        assert!(matches!(outcome, StepOutcome::Succeeded));
+        let written = std::fs::read_to_string(output.join("out.csv")).unwrap();
+        let _ = std::fs::remove_dir_all(&output);
+        assert_eq!(written, "a\nb\n");
+
+        let events = sink.events();
+        let kinds: Vec<&str> = events.iter().map(event_kind).collect();
+        assert_eq!(
+            kinds,
+            ["StepStarted", "Progress", "TaskFinished", "Progress", "TaskFinished", "Progress", "StepEnded"]
+        );
+        let Some(StepEvent::Progress { stats }) = events.get(5) else { panic!("no final progress") };
+        assert_eq!(stats.succeeded, 2);
+        assert_eq!(stats.failed, 0);
+    }
+
+    #[tokio::test]
+    async fn run_pipeline_reports_steps_that_did_not_succeed() {
+        let output = std::env::temp_dir().join(format!("tr-scraping-run-pipeline-{}", std::process::id()));
+        let csv = |name: &str| serde_json::json!({ "CsvJob": { "keys": ["K"], "sep": ",", "filename": "out.csv", "output": output.join(name).to_string_lossy() } });
+        let setting: Setting = serde_json::from_value(serde_json::json!({
+            "env": {},
+            "header": {},
+            "edges": [["bad", "child"]],
+            "steps": {
+                "ok": { "name": "ok", "task_iters": [{ "Vec": { "name": "K", "val": ["a"] } }], "job": csv("ok"), "concurrency_limit": 1 },
+                "bad": {
+                    "name": "bad",
+                    "on_iter_error": "Abort",
+                    "task_iters": [{ "GlobJsonPattern": { "glob_pattern": output.join("missing/*.json").to_string_lossy(), "item_pattern": "$[*]", "env_pattern": {} } }],
+                    "job": csv("bad"),
+                    "concurrency_limit": 1
+                },
+                "child": { "name": "child", "task_iters": [{ "Vec": { "name": "K", "val": ["a"] } }], "job": csv("child"), "concurrency_limit": 1 }
+            }
+        }))
+        .unwrap();
+        let mut scraping = Scraping::new();
+        scraping.load(setting).await.unwrap();
+
+        let sink = Arc::new(MemorySink::new());
+        let res = scraping.run_pipeline(sink.clone()).await;
+        let _ = std::fs::remove_dir_all(&output);
+        let Err(ApiError::ScrapingError(e)) = res else { panic!("pipeline did not fail: {:?}", res) };
+        assert!(e.contains("bad failed"), "{}", e);
+        assert!(e.contains("child skipped"), "{}", e);
+        assert!(scraping.pipeline_cancel.read().await.is_none());
+
+        // a second pipeline is rejected while one is running
+        *scraping.pipeline_cancel.write().await = Some(CancellationToken::new());
+        let res = scraping.run_pipeline(sink).await;
+        assert!(matches!(res, Err(ApiError::ScrapingError(e)) if e.contains("already running")));
+    }
+}
@@ -0,0 +1,8 @@
+use std::sync::Arc;
+use crate::models::StepEvent;
+
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: StepEvent);
+}
+
+pub type SharedSink = Arc<dyn EventSink>;
@@ -0,0 +1,30 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::models::{Result, StepEvent};
+use crate::sinks::event_sink::EventSink;
+
+pub struct JsonLinesSink {
+    pub file: Mutex<File>,
+}
+
+impl JsonLinesSink {
+    pub fn new(path: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)?;
+        Ok(JsonLinesSink { file: Mutex::new(file) })
+    }
+}
+
+impl EventSink for JsonLinesSink {
+    fn emit(&self, event: StepEvent) {
+        let Ok(line) = serde_json::to_string(&event) else { return; };
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = writeln!(file, "{}", line) {
+            eprintln!("Error: {:?}", e);
+        }
+    }
+}
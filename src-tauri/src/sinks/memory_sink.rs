@@ -0,0 +1,25 @@
+use std::sync::Mutex;
+
+use crate::models::StepEvent;
+use crate::sinks::event_sink::EventSink;
+
+#[derive(Default)]
+pub struct MemorySink {
+    pub events: Mutex<Vec<StepEvent>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        MemorySink::default()
+    }
+
+    pub fn events(&self) -> Vec<StepEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl EventSink for MemorySink {
+    fn emit(&self, event: StepEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod event_sink;
+#[cfg(feature = "gui")]
+pub mod window_sink;
+pub mod memory_sink;
+pub mod json_lines_sink;
+pub mod stdout_sink;
@@ -0,0 +1,36 @@
+use crate::models::StepEvent;
+use crate::sinks::event_sink::EventSink;
+
+pub struct StdoutSink;
+
+impl EventSink for StdoutSink {
+    fn emit(&self, event: StepEvent) {
+        match event {
+            StepEvent::StepWaiting { step, parents } => println!("[{}] wait {:?}", step, parents),
+            StepEvent::StepStarted { step } => println!("[{}] start", step),
+            StepEvent::IterError { step, error } => println!("[{}] iter error {}", step, error),
+            StepEvent::TaskFinished { step, message } => println!("[{}] done {}", step, message),
+            StepEvent::TaskSkipped { step, message } => println!("[{}] skip {}", step, message),
+            StepEvent::TaskRetry { step, message, attempt, delay_ms, error } => {
+                println!("[{}] retry {} attempt {} in {}ms {}", step, message, attempt, delay_ms, error)
+            }
+            StepEvent::TaskFailed { step, message, error } => {
+                println!("[{}] fail {} {}", step, message, error)
+            }
+            StepEvent::StepEnded { step } => println!("[{}] end", step),
+            StepEvent::StepStopped { step } => println!("[{}] stop", step),
+            StepEvent::StepSkipped { step } => println!("[{}] skip", step),
+            StepEvent::StepFailed { step, error } => println!("[{}] fail {}", step, error),
+            StepEvent::Progress { stats } => println!(
+                "[{}] progress {}/{} failed {} skipped {} running {} eta {}ms",
+                stats.step,
+                stats.succeeded + stats.failed + stats.skipped,
+                stats.total.map(|total| total.to_string()).unwrap_or("?".to_string()),
+                stats.failed,
+                stats.skipped,
+                stats.running,
+                stats.eta_ms.map(|eta_ms| eta_ms.to_string()).unwrap_or("?".to_string()),
+            ),
+        }
+    }
+}
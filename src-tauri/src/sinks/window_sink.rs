@@ -0,0 +1,73 @@
+use tauri::Emitter;
+
+use crate::models::{StepEvent, StepNotify};
+use crate::sinks::event_sink::EventSink;
+
+pub struct WindowSink {
+    pub window: tauri::Window,
+}
+
+impl WindowSink {
+    pub fn new(window: tauri::Window) -> Self {
+        WindowSink { window }
+    }
+}
+
+impl EventSink for WindowSink {
+    fn emit(&self, event: StepEvent) {
+        for notify in to_notifies(&event) {
+            if let Err(e) = self.window.emit(&notify.name.clone(), notify) {
+                eprintln!("Error: {:?}", e);
+            }
+        }
+        if let Err(e) = self.window.emit("step_event", event) {
+            eprintln!("Error: {:?}", e);
+        }
+    }
+}
+
+fn to_notifies(event: &StepEvent) -> Vec<StepNotify> {
+    let status = |status: &str, message: String| StepNotify {
+        name: "status".to_string(),
+        status: status.to_string(),
+        message,
+    };
+    let progress = |message: String| StepNotify {
+        name: "progress".to_string(),
+        status: "".to_string(),
+        message,
+    };
+    match event {
+        StepEvent::StepWaiting { step, parents } => {
+            vec![status("wait", format!("Wait Step {} {:?}", step, parents))]
+        }
+        StepEvent::StepStarted { step } => vec![status("start", format!("Start Step {}", step))],
+        StepEvent::IterError { error, .. } => vec![StepNotify {
+            name: "error".to_string(),
+            status: "iter".to_string(),
+            message: error.clone(),
+        }],
+        StepEvent::TaskFinished { message, .. } => vec![progress(message.clone())],
+        StepEvent::TaskSkipped { message, .. } => vec![progress(message.clone())],
+        StepEvent::TaskRetry { message, attempt, delay_ms, error, .. } => vec![StepNotify {
+            name: "progress".to_string(),
+            status: "retry".to_string(),
+            message: format!("{} attempt {} failed, retry in {}ms: {}", message, attempt, delay_ms, error),
+        }],
+        StepEvent::TaskFailed { message, error, .. } => vec![
+            StepNotify {
+                name: "error".to_string(),
+                status: "".to_string(),
+                message: error.clone(),
+            },
+            progress(message.clone()),
+        ],
+        StepEvent::StepEnded { step } => vec![status("end", format!("End Step {}", step))],
+        StepEvent::StepStopped { step } => vec![status("stop", format!("Stop Step {}", step))],
+        StepEvent::StepSkipped { step } => vec![status("skip", format!("Skip Step {}", step))],
+        StepEvent::StepFailed { step, error } => {
+            vec![status("fail", format!("Fail Step {} {}", step, error))]
+        }
+        StepEvent::Progress { .. } => vec![],
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::models::StepStats;
+
+#[derive(Default, Debug)]
+pub struct StepCounters {
+    pub running: AtomicU32,
+    pub succeeded: AtomicU32,
+    pub skipped: AtomicU32,
+    pub failed: AtomicU32,
+    pub total: Mutex<Option<u32>>,
+    pub started: Mutex<Option<Instant>>,
+}
+
+impl StepCounters {
+    pub fn reset(&self, total: Option<u32>) {
+        self.running.store(0, Ordering::SeqCst);
+        self.succeeded.store(0, Ordering::SeqCst);
+        self.skipped.store(0, Ordering::SeqCst);
+        self.failed.store(0, Ordering::SeqCst);
+        *self.total.lock().unwrap() = total;
+        *self.started.lock().unwrap() = Some(Instant::now());
+    }
+
+    pub fn incr(counter: &AtomicU32) {
+        counter.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn decr(counter: &AtomicU32) {
+        let _ = counter.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| v.checked_sub(1));
+    }
+
+    pub fn snapshot(&self, step: &str) -> StepStats {
+        let running = self.running.load(Ordering::SeqCst);
+        let succeeded = self.succeeded.load(Ordering::SeqCst);
+        let skipped = self.skipped.load(Ordering::SeqCst);
+        let failed = self.failed.load(Ordering::SeqCst);
+        let total = *self.total.lock().unwrap();
+        let elapsed = self
+            .started
+            .lock()
+            .unwrap()
+            .map(|started| started.elapsed())
+            .unwrap_or_default();
+
+        let processed = succeeded + failed;
+        // tasks not started yet; without a total the iterators haven't been walked ahead
+        let queued = total.map(|total| total.saturating_sub(processed + skipped + running));
+        let elapsed_secs = elapsed.as_secs_f64();
+        let throughput = if elapsed_secs > 0.0 { processed as f64 / elapsed_secs } else { 0.0 };
+        let eta_ms = match total {
+            Some(total) if throughput > 0.0 => {
+                let remaining = total.saturating_sub(processed + skipped);
+                Some((remaining as f64 / throughput * 1000.0) as u32)
+            }
+            _ => None,
+        };
+
+        StepStats {
+            step: step.to_string(),
+            total,
+            queued,
+            running,
+            succeeded,
+            skipped,
+            failed,
+            elapsed_ms: elapsed.as_millis() as u32,
+            throughput,
+            eta_ms,
+        }
+    }
+}
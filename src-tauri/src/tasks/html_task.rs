@@ -68,11 +68,11 @@ impl HtmlTask {
         let p_tmp = Path::new(tmp_path.as_str());
         if p.exists() {
             // return Ok(());
-            let _ = std::fs::remove_file(p).map_err(|e| println!("{:?}", e));
+            let _ = std::fs::remove_file(p).map_err(|e| eprintln!("{:?}", e));
         }
 
         if p_tmp.exists() {
-            let _ = std::fs::remove_file(p_tmp).map_err(|e| println!("{:?}", e));
+            let _ = std::fs::remove_file(p_tmp).map_err(|e| eprintln!("{:?}", e));
         }
 
         let template = self.html_template.clone();
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::path::Path;
+use reqwest::multipart::{Form, Part};
+use reqwest::RequestBuilder;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use crate::models::{ApiError, Result};
+use crate::utils::{get_handlebars_json, get_handlebars_raw};
+
+/// Request body; every string is a template over the task env.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub enum HttpBody {
+    Raw { text: String, content_type: Option<String> },
+    /// JSON text; values are JSON-escaped, so `"{{Q}}"` is safe for any Q and `{{PAGE}}` stays a number
+    Json(String),
+    Form(HashMap<String, String>),
+    Multipart(Vec<MultipartPart>),
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct MultipartPart {
+    pub name: String,
+    pub value: Option<String>,
+    /// path of a file to upload instead of `value`
+    pub file: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub enum RenderedBody {
+    Raw { text: String, content_type: Option<String> },
+    Json(Value),
+    Form(Vec<(String, String)>),
+    Multipart(Vec<MultipartPart>),
+}
+
+impl HttpBody {
+    pub fn render(&self, env: &HashMap<String, String>) -> Result<RenderedBody> {
+        match self {
+            HttpBody::Raw { text, content_type } => Ok(RenderedBody::Raw {
+                text: get_handlebars_raw(text, env)?,
+                content_type: content_type.clone(),
+            }),
+            HttpBody::Json(text) => {
+                let rendered = get_handlebars_json(text, env)?;
+                let json = serde_json::from_str(&rendered)
+                    .map_err(|e| ApiError::JsonError(format!("body: {}: {}", e, rendered)))?;
+                Ok(RenderedBody::Json(json))
+            }
+            HttpBody::Form(fields) => {
+                let mut rendered = Vec::new();
+                for (k, v) in fields.iter() {
+                    rendered.push((k.clone(), get_handlebars_raw(v, env)?));
+                }
+                rendered.sort();
+                Ok(RenderedBody::Form(rendered))
+            }
+            HttpBody::Multipart(parts) => {
+                let mut rendered = Vec::new();
+                for part in parts.iter() {
+                    let render = |s: &Option<String>| s.as_ref().map(|s| get_handlebars_raw(s, env)).transpose();
+                    if part.value.is_none() == part.file.is_none() {
+                        return Err(ApiError::ScrapingError(format!("multipart {}: set one of value or file", part.name)));
+                    }
+                    rendered.push(MultipartPart {
+                        name: part.name.clone(),
+                        value: render(&part.value)?,
+                        file: render(&part.file)?,
+                        filename: render(&part.filename)?,
+                        content_type: part.content_type.clone(),
+                    });
+                }
+                Ok(RenderedBody::Multipart(rendered))
+            }
+        }
+    }
+}
+
+impl RenderedBody {
+    pub async fn apply(&self, req_builder: RequestBuilder) -> Result<RequestBuilder> {
+        let req_builder = match self {
+            RenderedBody::Raw { text, content_type } => {
+                let req_builder = match content_type {
+                    Some(content_type) => req_builder.header(reqwest::header::CONTENT_TYPE, content_type),
+                    None => req_builder,
+                };
+                req_builder.body(text.clone())
+            }
+            RenderedBody::Json(json) => req_builder.json(json),
+            RenderedBody::Form(fields) => req_builder.form(fields),
+            // `Form` can't be cloned, so it is rebuilt for every attempt
+            RenderedBody::Multipart(parts) => req_builder.multipart(multipart_form(parts).await?),
+        };
+        Ok(req_builder)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RenderedBody::Raw { text, .. } => text.clone(),
+            RenderedBody::Json(json) => json.to_string(),
+            RenderedBody::Form(fields) => fields
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join("&"),
+            RenderedBody::Multipart(parts) => parts
+                .iter()
+                .map(|part| match &part.file {
+                    Some(file) => format!("{}=@{}", part.name, file),
+                    None => format!("{}={}", part.name, part.value.clone().unwrap_or_default()),
+                })
+                .collect::<Vec<_>>()
+                .join("; "),
+        }
+    }
+}
+
+async fn multipart_form(parts: &[MultipartPart]) -> Result<Form> {
+    let mut form = Form::new();
+    for part in parts.iter() {
+        let mut form_part = match &part.file {
+            Some(file) => {
+                let bytes = tokio::fs::read(file).await?;
+                let filename = part.filename.clone().unwrap_or_else(|| {
+                    Path::new(file)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                });
+                Part::bytes(bytes).file_name(filename)
+            }
+            None => Part::text(part.value.clone().unwrap_or_default()),
+        };
+        if let Some(content_type) = &part.content_type {
+            form_part = form_part.mime_str(content_type)?;
+        }
+        form = form.part(part.name.clone(), form_part);
+    }
+    Ok(form)
+}
@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderValue};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use crate::models::Result;
+use crate::tasks::http_body::RenderedBody;
+
+/// When a file that is already saved is requested again.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub enum RefreshMode {
+    /// keep the saved file
+    #[default]
+    Never,
+    /// request again once the response's max-age/Expires (or `max_age_s`) has passed
+    IfStale,
+    /// request again on every run
+    Always,
+}
+
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct CacheConfig {
+    /// `<output>/.http_cache` when omitted
+    pub dir: Option<String>,
+    /// request headers that are part of the cache key besides method, url and body
+    pub vary: Option<Vec<String>>,
+    /// freshness when the response has no Cache-Control max-age or Expires
+    pub max_age_s: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CacheEntry {
+    pub key: String,
+    pub save_path: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub stored_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+impl CacheEntry {
+    pub fn is_fresh(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| Utc::now().timestamp() < expires_at)
+    }
+
+    /// `If-None-Match` / `If-Modified-Since` for a conditional request.
+    pub fn conditional_header(&self) -> HeaderMap {
+        let mut header = HeaderMap::new();
+        if let Some(val) = self.etag.as_ref().and_then(|v| HeaderValue::from_str(v).ok()) {
+            header.insert(reqwest::header::IF_NONE_MATCH, val);
+        }
+        if let Some(val) = self.last_modified.as_ref().and_then(|v| HeaderValue::from_str(v).ok()) {
+            header.insert(reqwest::header::IF_MODIFIED_SINCE, val);
+        }
+        header
+    }
+}
+
+/// One cache entry file per method + url + request body + vary headers; the response body itself is the task's saved file.
+#[derive(Clone, Debug)]
+pub struct HttpCache {
+    pub dir: String,
+    pub key: String,
+    pub max_age_s: Option<u32>,
+}
+
+impl HttpCache {
+    pub fn new(
+        config: &Option<CacheConfig>,
+        folder: &str,
+        method: &str,
+        url: &str,
+        header: &HeaderMap,
+        body: Option<&RenderedBody>,
+    ) -> Self {
+        let mut key = format!("{} {}", method, url);
+        // POSTs to one url with different bodies are different requests
+        if let Some(body) = body {
+            key.push_str(&format!("\nbody: {:016x}", fnv1a(body.describe().as_bytes())));
+        }
+        let mut vary = config.as_ref().and_then(|c| c.vary.clone()).unwrap_or_default();
+        vary.sort();
+        for name in vary.iter() {
+            let val = header.get(name.as_str()).and_then(|v| v.to_str().ok()).unwrap_or_default();
+            key.push_str(&format!("\n{}: {}", name.to_lowercase(), val));
+        }
+        let dir = match config.as_ref().and_then(|c| c.dir.clone()) {
+            Some(dir) => dir,
+            None => Path::new(folder).join(".http_cache").to_string_lossy().to_string(),
+        };
+        HttpCache {
+            dir,
+            key,
+            max_age_s: config.as_ref().and_then(|c| c.max_age_s),
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        Path::new(&self.dir).join(format!("{:016x}.cache", fnv1a(self.key.as_bytes())))
+    }
+
+    /// Entry for this key whose response was saved to `save_path`.
+    pub fn get(&self, save_path: &str) -> Option<CacheEntry> {
+        let text = std::fs::read_to_string(self.path()).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&text).ok()?;
+        if entry.key != self.key || entry.save_path != save_path {
+            return None;
+        }
+        Some(entry)
+    }
+
+    /// Records a 200 or 304 response; a 304 keeps the validators it does not resend.
+    pub fn put(&self, save_path: &str, header: &HeaderMap, prev: Option<&CacheEntry>) -> Result<()> {
+        let get = |name: reqwest::header::HeaderName| {
+            header.get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string())
+        };
+        let stored_at = Utc::now().timestamp();
+        let entry = CacheEntry {
+            key: self.key.clone(),
+            save_path: save_path.to_string(),
+            etag: get(reqwest::header::ETAG).or(prev.and_then(|p| p.etag.clone())),
+            last_modified: get(reqwest::header::LAST_MODIFIED).or(prev.and_then(|p| p.last_modified.clone())),
+            stored_at,
+            expires_at: self.expires_at(header, stored_at),
+        };
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(), serde_json::to_string_pretty(&entry)?)?;
+        Ok(())
+    }
+
+    fn expires_at(&self, header: &HeaderMap, stored_at: i64) -> Option<i64> {
+        let cache_control = header
+            .get(reqwest::header::CACHE_CONTROL)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_lowercase();
+        for directive in cache_control.split(',').map(|d| d.trim()) {
+            if directive == "no-cache" || directive == "no-store" {
+                return Some(stored_at);
+            }
+            if let Some(max_age) = directive.strip_prefix("max-age=").and_then(|v| v.parse::<i64>().ok()) {
+                return Some(stored_at + max_age);
+            }
+        }
+        let expires = header
+            .get(reqwest::header::EXPIRES)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok());
+        if let Some(expires) = expires {
+            return Some(expires.timestamp());
+        }
+        self.max_age_s.map(|max_age_s| stored_at + max_age_s as i64)
+    }
+}
+
+/// FNV-1a, stable across runs and platforms unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_includes_request_body() {
+        let header = HeaderMap::new();
+        let body = |q: &str| RenderedBody::Form(vec![("q".to_string(), q.to_string())]);
+        let a = HttpCache::new(&None, "out", "POST", "http://e.com/search", &header, Some(&body("a")));
+        let b = HttpCache::new(&None, "out", "POST", "http://e.com/search", &header, Some(&body("b")));
+        let a2 = HttpCache::new(&None, "out", "POST", "http://e.com/search", &header, Some(&body("a")));
+        let none = HttpCache::new(&None, "out", "POST", "http://e.com/search", &header, None);
+        assert_ne!(a.key, b.key);
+        assert_ne!(a.key, none.key);
+        assert_eq!(a.key, a2.key);
+        assert_ne!(a.path(), b.path());
+        // a `*.json` glob over the output folder must not match cache entries
+        assert_eq!(a.path().extension().unwrap(), "cache");
+    }
+}
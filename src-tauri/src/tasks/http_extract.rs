@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use crate::models::{ApiError, Result};
+use crate::utils::get_json_val;
+
+/// Where a value is taken from in the response.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub enum Extractor {
+    JsonPath(String),
+    Header(String),
+    /// first capture group, or the whole match when the regex has no groups
+    Regex(String),
+}
+
+pub fn needs_header(extract: &HashMap<String, Extractor>) -> bool {
+    extract.values().any(|extractor| matches!(extractor, Extractor::Header(_)))
+}
+
+pub fn header_values(header: &HeaderMap) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    for (k, v) in header.iter() {
+        if let Ok(v) = v.to_str() {
+            values.entry(k.as_str().to_string()).or_insert(v.to_string());
+        }
+    }
+    values
+}
+
+pub fn extract_values(
+    extract: &HashMap<String, Extractor>,
+    body: &str,
+    header: &HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+    let mut json: Option<Value> = None;
+    let mut values = HashMap::new();
+    for (name, extractor) in extract.iter() {
+        let value = match extractor {
+            Extractor::JsonPath(path) => {
+                if json.is_none() {
+                    json = Some(serde_json::from_str(body)?);
+                }
+                json.as_ref().and_then(|json| get_json_val(json, path))
+            }
+            Extractor::Header(header_name) => header.get(&header_name.to_lowercase()).cloned(),
+            Extractor::Regex(pattern) => {
+                let re = Regex::new(pattern).map_err(|e| ApiError::ParseError(format!("{}: {}", pattern, e)))?;
+                re.captures(body).and_then(|caps| caps.get(1).or(caps.get(0))).map(|m| m.as_str().to_string())
+            }
+        };
+        let Some(value) = value else {
+            return Err(ApiError::ScrapingError(format!("extract {}: no match for {:?}", name, extractor)));
+        };
+        values.insert(name.clone(), value);
+    }
+    Ok(values)
+}
@@ -2,10 +2,11 @@ use std::collections::HashMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 use encoding_rs::Encoding;
 use mime::Mime;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use reqwest::{Client, RequestBuilder};
+use reqwest::{Client, Method, Response, StatusCode};
 use sanitize_filename::sanitize;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -13,8 +14,15 @@ use serde_json::Value;
 use specta::Type;
 use crate::models::ApiError;
 use crate::models::Result;
-use crate::utils::{get_handlebars, get_handlebars_safe_dir};
-use crate::tasks::task::{Task};
+use crate::utils::{get_handlebars, get_handlebars_safe_dir, get_json_val, uses_var};
+use crate::tasks::task::{Task, TaskContext};
+use crate::tasks::http_body::{HttpBody, RenderedBody};
+use crate::tasks::http_cache::{CacheConfig, HttpCache, RefreshMode};
+use crate::tasks::http_extract::{extract_values, header_values, needs_header, Extractor};
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::auth::AuthSession;
+use crate::cookies::CookieJar;
+use crate::retry::parse_retry_after;
 
 #[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct HttpJob {
@@ -23,21 +31,158 @@ pub struct HttpJob {
     pub header: HashMap<String, String>,
     pub filename: String,
     pub output: String,
+    pub body: Option<HttpBody>,
+    pub pagination: Option<Pagination>,
+    /// values taken from the response and added to the env of `then`
+    pub extract: Option<HashMap<String, Extractor>>,
+    /// requests made after this one in the same task, e.g. a detail page then its attachment
+    pub then: Option<Vec<HttpJob>>,
+    /// what to do when the file is already saved, `Never` when omitted
+    pub refresh: Option<RefreshMode>,
+    pub cache: Option<CacheConfig>,
+    /// send a `method` outside GET/POST/PUT/PATCH/DELETE/HEAD/OPTIONS/CONNECT/TRACE as is
+    #[serde(default)]
+    pub allow_custom_method: bool,
+}
+
+/// Fetches page after page, feeding `cursor_name`/`page_name` into the url, header and filename templates.
+#[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Pagination {
+    pub cursor_name: String,
+    pub page_name: Option<String>,
+    /// first cursor, "" when omitted
+    pub start: Option<String>,
+    /// JSONPath to the next cursor; stops when missing, empty or unchanged
+    pub next_cursor: Option<String>,
+    /// JSONPath to a has-more flag; stops when false, 0 or missing
+    pub has_more: Option<String>,
+    /// JSONPath to the page items; stops when it selects nothing
+    pub items: Option<String>,
+    /// at least 1; no limit when omitted
+    pub max_pages: Option<u32>,
+}
+
+impl Pagination {
+    pub fn page_name(&self) -> String {
+        self.page_name.clone().unwrap_or("PAGE".to_string())
+    }
+
+    fn page_env(&self, cur_env: &HashMap<String, String>, cursor: &str, page: u32) -> HashMap<String, String> {
+        let mut env = cur_env.clone();
+        env.insert(self.cursor_name.clone(), cursor.to_string());
+        env.insert(self.page_name(), page.to_string());
+        env
+    }
+
+    /// Next cursor, or None when the page says there is nothing more to fetch.
+    fn next(&self, json: &Value, cursor: &str) -> Option<String> {
+        if let Some(items) = &self.items {
+            let selected = jsonpath_lib::select(json, items).ok()?;
+            let is_empty = match selected.as_slice() {
+                [] => true,
+                [Value::Array(arr)] => arr.is_empty(),
+                _ => false,
+            };
+            if is_empty {
+                return None;
+            }
+        }
+        if let Some(has_more) = &self.has_more {
+            let flag = get_json_val(json, has_more)?;
+            if matches!(flag.as_str(), "" | "false" | "0" | "null") {
+                return None;
+            }
+        }
+        match &self.next_cursor {
+            Some(next_cursor) => {
+                let next = get_json_val(json, next_cursor)?;
+                if next.is_empty() || next == "null" || next == cursor {
+                    return None;
+                }
+                Some(next)
+            }
+            None => Some(cursor.to_string()),
+        }
+    }
 }
 
 impl HttpJob {
     pub fn pre_process(&mut self) -> Result<()> {
+        if let Some(pagination) = &self.pagination {
+            if pagination.next_cursor.is_none() && pagination.has_more.is_none() && pagination.items.is_none() {
+                return Err(ApiError::ScrapingError("pagination: next_cursor, has_more or items is required".to_string()));
+            }
+            if pagination.max_pages == Some(0) {
+                return Err(ApiError::ScrapingError("pagination: max_pages must be at least 1".to_string()));
+            }
+            let page_name = pagination.page_name();
+            // without next_cursor the cursor never changes, so only the page number moves the request on
+            let mut names = vec![page_name];
+            if pagination.next_cursor.is_some() {
+                names.push(pagination.cursor_name.clone());
+            }
+            let uses = |template: &str| names.iter().any(|name| uses_var(template, name));
+            let names = names.iter().map(|name| format!("{{{{{}}}}}", name)).collect::<Vec<_>>().join(" or ");
+            if !uses(&self.filename) {
+                return Err(ApiError::ScrapingError(format!("pagination: filename must use {}", names)));
+            }
+            let body = serde_json::to_string(&self.body)?;
+            if !uses(&self.url) && !uses(&body) {
+                return Err(ApiError::ScrapingError(format!("pagination: url or body must use {}", names)));
+            }
+            if self.extract.is_some() || self.then.is_some() {
+                return Err(ApiError::ScrapingError("pagination: extract and then are not supported".to_string()));
+            }
+        }
+        for job in self.then.iter_mut().flatten() {
+            job.pre_process()?;
+        }
         Ok(())
     }
 
-    pub async fn make_task(&self, cur_env: HashMap<String, String>, client: Client, g_header: HashMap<String, String>) -> Result<Task> {
+    pub async fn make_task(&self, cur_env: HashMap<String, String>, ctx: &TaskContext) -> Result<Task> {
+        let Some(pagination) = &self.pagination else {
+            let mut task = self.make_http_task(cur_env.clone(), ctx)?;
+            if self.extract.is_some() || self.then.is_some() {
+                task.chain = Some(HttpChain {
+                    extract: self.extract.clone().unwrap_or_default(),
+                    then: self.then.clone().unwrap_or_default(),
+                    cur_env,
+                    ctx: ctx.clone(),
+                });
+            }
+            return Ok(Task::HttpTask(task));
+        };
+        let start = get_handlebars(&pagination.start.clone().unwrap_or_default(), &cur_env)?;
+        let mut task = self.make_http_task(pagination.page_env(&cur_env, &start, 0), ctx)?;
+        task.paging = Some(HttpPaging {
+            job: HttpJob { pagination: None, ..self.clone() },
+            pagination: pagination.clone(),
+            start,
+            cur_env,
+            ctx: ctx.clone(),
+        });
+        Ok(Task::HttpTask(task))
+    }
+
+    fn make_http_task(&self, mut cur_env: HashMap<String, String>, ctx: &TaskContext) -> Result<HttpTask> {
+        // a token refreshed during the step is newer than the env the step started with
+        if let Some(auth) = &ctx.auth {
+            if let Some(token) = auth.current() {
+                cur_env.insert(auth.token_name(), token);
+            }
+        }
 
         let url = get_handlebars(&self.url, &cur_env)?;
 
-        let method = self.method.clone();
+        let method = parse_method(&self.method, self.allow_custom_method)?;
+        let body = match &self.body {
+            Some(body) => Some(body.render(&cur_env)?),
+            None => None,
+        };
 
         let mut header = HeaderMap::new();
-        for (k, v) in g_header.iter() {
+        for (k, v) in ctx.header.iter() {
             let nm = HeaderName::from_str(k.as_str())?;
             let new_v = get_handlebars(v, &cur_env)?;
             let val = HeaderValue::from_str(&new_v)?;
@@ -54,31 +199,201 @@ impl HttpJob {
         let filename = sanitize(get_handlebars(&self.filename, &cur_env)?);
         let p: PathBuf = Path::new(&folder).join(filename);
         let save_path = p.to_string_lossy().to_string();
+        let cache = HttpCache::new(&self.cache, &folder, method.as_str(), &url, &header, body.as_ref());
 
-        Ok(Task::HttpTask(HttpTask {
-            client,
+        Ok(HttpTask {
+            client: ctx.client.clone(),
+            rate_limiter: ctx.rate_limiter.clone(),
+            rate_limit: ctx.rate_limit.clone(),
+            auth: ctx.auth.clone(),
+            cookie_jar: ctx.cookie_jar.clone(),
             url,
             method,
             header,
+            body,
             folder,
             save_path,
-        }))
+            refresh: self.refresh.clone().unwrap_or_default(),
+            cache,
+            paging: None,
+            chain: None,
+        })
+    }
+}
+
+/// Headers only known at send time: the auth header for the current token, and the cookie jar
+/// merged into an explicit `Cookie` header (reqwest skips the jar when one is set).
+pub async fn send_header(
+    mut header: HeaderMap,
+    url: &str,
+    auth: Option<(&AuthSession, &str)>,
+    cookie_jar: &CookieJar,
+) -> Result<HeaderMap> {
+    if let Some((auth, token)) = auth {
+        header.extend(auth.header(token).await?);
+    }
+    let cookie = header.get(reqwest::header::COOKIE).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    if let Some(cookie) = cookie {
+        let url = reqwest::Url::parse(url).map_err(|e| ApiError::ParseError(e.to_string()))?;
+        let merged = cookie_jar.merge_cookie_header(&cookie, &url);
+        header.insert(reqwest::header::COOKIE, HeaderValue::from_str(&merged)?);
+    }
+    Ok(header)
+}
+
+/// Parses `method` case-insensitively; a typo like `GTE` is an error unless `allow_custom` is set.
+pub fn parse_method(method: &str, allow_custom: bool) -> Result<Method> {
+    let parsed = Method::from_str(&method.to_uppercase())
+        .map_err(|e| ApiError::ParseError(format!("method {}: {}", method, e)))?;
+    let standard = [
+        Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE,
+        Method::HEAD, Method::OPTIONS, Method::CONNECT, Method::TRACE,
+    ];
+    if !allow_custom && !standard.contains(&parsed) {
+        return Err(ApiError::ParseError(format!("unknown method {}; set allow_custom_method to send it", method)));
+    }
+    Ok(parsed)
+}
+
+#[derive(Clone, Debug)]
+pub struct HttpPaging {
+    pub job: HttpJob,
+    pub pagination: Pagination,
+    pub start: String,
+    pub cur_env: HashMap<String, String>,
+    pub ctx: TaskContext,
+}
+
+impl HttpPaging {
+    pub async fn run(&self) -> Result<()> {
+        let mut cursor = self.start.clone();
+        let mut page = 0;
+        loop {
+            let env = self.pagination.page_env(&self.cur_env, &cursor, page);
+            let task = self.job.make_http_task(env, &self.ctx)?;
+            eprintln!("page {}: {}", page, &task.url);
+            // pages saved by an earlier run are not fetched again, only read for the next cursor
+            task.fetch().await?;
+            page += 1;
+            if self.pagination.max_pages.is_some_and(|max_pages| page >= max_pages) {
+                break;
+            }
+
+            let text = std::fs::read_to_string(&task.save_path)?;
+            let json: Value = serde_json::from_str(&text)?;
+            match self.pagination.next(&json, &cursor) {
+                Some(next) => cursor = next,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A response that was just downloaded, as written to `save_path`.
+pub struct Fetched {
+    pub header: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HttpChain {
+    pub extract: HashMap<String, Extractor>,
+    pub then: Vec<HttpJob>,
+    pub cur_env: HashMap<String, String>,
+    pub ctx: TaskContext,
+}
+
+impl HttpChain {
+    /// `fetched` is None when the saved file was kept; the body is then read from it and header
+    /// extractors read the headers saved by the run that fetched it.
+    pub async fn run(&self, task: &HttpTask, fetched: Option<Fetched>) -> Result<()> {
+        let p = Path::new(&task.save_path);
+        let header_dir = p.parent().unwrap_or(Path::new("")).join(".http_headers");
+        let file_name = p.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let header_path = header_dir.join(format!("{}.headers", file_name));
+        let (header, bytes) = match fetched {
+            Some(fetched) => {
+                let header = header_values(&fetched.header);
+                if needs_header(&self.extract) {
+                    std::fs::create_dir_all(&header_dir)?;
+                    std::fs::write(&header_path, serde_json::to_string_pretty(&header)?)?;
+                }
+                (header, fetched.body)
+            }
+            None => {
+                let header = if needs_header(&self.extract) && header_path.exists() {
+                    serde_json::from_str(&std::fs::read_to_string(&header_path)?)?
+                } else {
+                    HashMap::new()
+                };
+                (header, std::fs::read(p)?)
+            }
+        };
+
+        let body = String::from_utf8_lossy(&bytes);
+        let mut env = self.cur_env.clone();
+        env.extend(extract_values(&self.extract, &body, &header)?);
+        for job in self.then.iter() {
+            let mut next = job.make_task(env.clone(), &self.ctx).await?;
+            eprintln!("then: {}", next.describe());
+            Box::pin(next.run_task()).await?;
+        }
+        Ok(())
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct HttpTask {
     pub client: Client,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub rate_limit: Option<RateLimit>,
+    pub auth: Option<Arc<AuthSession>>,
+    pub cookie_jar: Arc<CookieJar>,
     pub url: String,
-    pub method: String,
+    pub method: Method,
     pub header: HeaderMap,
+    pub body: Option<RenderedBody>,
     pub folder: String,
     pub save_path: String,
+    pub refresh: RefreshMode,
+    pub cache: HttpCache,
+    pub paging: Option<HttpPaging>,
+    pub chain: Option<HttpChain>,
 }
 
 
 impl HttpTask {
     pub async fn run(&mut self) -> Result<()> {
+        if let Some(paging) = &self.paging {
+            return paging.run().await;
+        }
+        let fetched = self.fetch().await?;
+        if let Some(chain) = &self.chain {
+            chain.run(self, fetched).await?;
+        }
+        Ok(())
+    }
+
+    async fn send(&self, token: Option<&str>, conditional: &HeaderMap) -> Result<Response> {
+        if let Some(rate_limit) = &self.rate_limit {
+            self.rate_limiter.acquire_url(&self.url, rate_limit).await?;
+        }
+
+        let mut header = self.header.clone();
+        header.extend(conditional.clone());
+        let auth = self.auth.as_deref().zip(token);
+        let header = send_header(header, &self.url, auth, &self.cookie_jar).await?;
+        let mut req_builder = self.client.request(self.method.clone(), &self.url);
+        req_builder = req_builder.headers(header);
+        if let Some(body) = &self.body {
+            req_builder = body.apply(req_builder).await?;
+        }
+        Ok(req_builder.send().await?)
+    }
+
+    /// Returns the response as saved, or None when the saved file was kept.
+    pub async fn fetch(&self) -> Result<Option<Fetched>> {
         let folder = self.folder.clone();
         let p_folder = Path::new(&folder);
         if !p_folder.exists() {
@@ -89,26 +404,48 @@ impl HttpTask {
         let tmp_path = format!("{}.tmp", &save_path);
         let p = Path::new(&save_path);
         let p_tmp = Path::new(tmp_path.as_str());
-        if p.exists() {
-            return Ok(());
-        }
+        let entry = match (p.exists(), &self.refresh) {
+            (false, _) => None,
+            (true, RefreshMode::Never) => return Ok(None),
+            (true, RefreshMode::IfStale) => match self.cache.get(&save_path) {
+                Some(entry) if entry.is_fresh() => return Ok(None),
+                entry => entry,
+            },
+            (true, RefreshMode::Always) => self.cache.get(&save_path),
+        };
+        let conditional = entry.as_ref().map(|entry| entry.conditional_header()).unwrap_or_default();
 
         if p_tmp.exists() {
-            let _ = std::fs::remove_file(p_tmp).map_err(|e| println!("{:?}", e));
+            let _ = std::fs::remove_file(p_tmp).map_err(|e| eprintln!("{:?}", e));
         }
 
-        let mut req_builder: RequestBuilder;
-        if self.method == "POST" {
-            req_builder = self.client.post(&self.url);
-        } else {
-            req_builder = self.client.get(&self.url);
+        let token = match &self.auth {
+            Some(auth) => Some(auth.token().await?),
+            None => None,
+        };
+        let mut res = self.send(token.as_deref(), &conditional).await?;
+        if res.status() == StatusCode::UNAUTHORIZED {
+            if let (Some(auth), Some(stale)) = (&self.auth, &token) {
+                eprintln!("auth: 401, logging in again");
+                let token = auth.refresh(stale).await?;
+                res = self.send(Some(&token), &conditional).await?;
+            }
+        }
+
+        if res.status() == StatusCode::NOT_MODIFIED && p.exists() {
+            eprintln!("not modified: {}", &self.url);
+            self.cache.put(&save_path, res.headers(), entry.as_ref())?;
+            return Ok(None);
         }
-        req_builder = req_builder.headers(self.header.clone());
-        let res = req_builder.send().await?;
 
         if !res.status().is_success() {
-            println!("run_task err: {:?} {:?}", res.status(), &self);
-            return Err(ApiError::ScrapingError(format!("status: {:?} {:?}", res.status(), &self.url)));
+            eprintln!("run_task err: {:?} {} {}", res.status(), &self.method, &self.url);
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(ApiError::HttpStatus(res.status().as_u16(), retry_after, self.url.clone()));
         }
 
         let mut charset: Option<String> = None;
@@ -118,41 +455,43 @@ impl HttpTask {
             .get(reqwest::header::CONTENT_TYPE)
             .and_then(|v| v.to_str().ok())
             .map(|s| s.to_string());
-        println!("content_type: {:?}", content_type);
+        eprintln!("content_type: {:?}", content_type);
         if let Some(content_type) = &content_type {
             charset = content_type
                 .clone()
                 .split("charset=")
                 .nth(1)
-                .and_then(|s| Some(s.to_string()));
+                .map(|s| s.to_string());
             if let Ok(mime) = content_type.clone().parse::<Mime>() {
                 mime_type = Some(mime.essence_str().to_string());
             };
         }
         // let body = res.text().await?;
         // println!("body: {:?}", &body);
+        let header = res.headers().clone();
         let bytes = res.bytes().await?;
 
 
-        if Some("application/json".to_string()) == mime_type {
+        // HEAD and 204 responses carry a JSON content type but no body to reformat
+        let saved = if Some("application/json".to_string()) == mime_type && !bytes.is_empty() && self.method != Method::HEAD {
             let label = charset.unwrap_or("utf-8".to_string());
             let (text, _, _) = Encoding::for_label(label.as_bytes())
                 .unwrap_or(encoding_rs::UTF_8)
                 .decode(&bytes);
 
             let json_value: Value = serde_json::from_str(&text)?;
-            let formatted = serde_json::to_string_pretty(&json_value)?;
-
-            let mut file = std::fs::File::create(p_tmp)?;
-            file.write_all(formatted.as_bytes())?;
-            std::fs::rename(p_tmp, p)?;
+            serde_json::to_string_pretty(&json_value)?.into_bytes()
         } else {
-            let mut file = std::fs::File::create(p_tmp)?;
-            file.write_all(&bytes)?;
-            std::fs::rename(p_tmp, p)?;
+            bytes.to_vec()
+        };
+        let mut file = std::fs::File::create(p_tmp)?;
+        file.write_all(&saved)?;
+        std::fs::rename(p_tmp, p)?;
+        if self.refresh != RefreshMode::Never {
+            self.cache.put(&save_path, &header, None)?;
         }
 
-        Ok(())
+        Ok(Some(Fetched { header, body: saved }))
     }
 }
 
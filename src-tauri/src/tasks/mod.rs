@@ -4,3 +4,6 @@ pub mod html_task;
 pub mod shell_task;
 pub mod csv_task;
 
+pub mod http_body;
+pub mod http_extract;
+pub mod http_cache;
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use crate::models::{ApiError, Result};
 use crate::tasks::task::{Task};
 use crate::utils::get_handlebars;
-use std::process::Command;
+use tokio::process::Command;
 use encoding::label::encoding_from_whatwg_label;
 use encoding::{DecoderTrap};
 
@@ -62,15 +62,31 @@ impl ShellTask {
         let output = Command::new(self.shell.clone())
             .args(self.args.clone())
             .current_dir(self.working_dir.clone())
+            .kill_on_drop(true)
             .output()
+            .await
             .map_err(|e| ApiError::ScrapingError(format!("{:?}", e)))?;
-        // let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let encoding = encoding_from_whatwg_label(&self.encoding)
             .unwrap_or(encoding::all::UTF_8);
-        let stdout = encoding
-            .decode(&output.stdout, DecoderTrap::Replace)
-            .map_err(|e| ApiError::ScrapingError(format!("{:?}", e)))?;
-        println!("{}", stdout);
+        let decode = |bytes: &[u8]| {
+            encoding
+                .decode(bytes, DecoderTrap::Replace)
+                .map_err(|e| ApiError::ScrapingError(format!("{:?}", e)))
+        };
+        if !output.status.success() {
+            let code = output.status.code().map_or("signal".to_string(), |code| code.to_string());
+            return Err(ApiError::ScrapingError(format!(
+                "{} exited with {}: {}",
+                self.shell,
+                code,
+                decode(&output.stderr)?.trim()
+            )));
+        }
+        // the engine's stdout belongs to the progress output, so the child's goes to stderr
+        let stdout = decode(&output.stdout)?;
+        if !stdout.trim().is_empty() {
+            eprintln!("{}", stdout.trim_end());
+        }
         Ok(())
     }
 }
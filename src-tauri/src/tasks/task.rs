@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use reqwest::Client;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -7,8 +8,23 @@ use crate::tasks::html_task::{HtmlJob, HtmlTask};
 use crate::tasks::http_task::{HttpJob, HttpTask};
 use crate::tasks::csv_task::{CsvJob, CsvTask};
 use crate::tasks::shell_task::{ShellJob, ShellTask};
-use crate::Result;
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::auth::AuthSession;
+use crate::cookies::CookieJar;
+use crate::models::{Result, TaskPreview};
 
+#[derive(Clone, Debug)]
+pub struct TaskContext {
+    pub client: Client,
+    pub header: HashMap<String, String>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub rate_limit: Option<RateLimit>,
+    pub auth: Option<Arc<AuthSession>>,
+    pub cookie_jar: Arc<CookieJar>,
+}
+
+// variant names are the setting's job names
+#[allow(clippy::enum_variant_names, clippy::large_enum_variant)]
 #[derive(Type, Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub enum Job {
     HttpJob(HttpJob),
@@ -27,9 +43,9 @@ impl Job {
         }
     }
 
-    pub async fn make_task(&self,  cur_env: HashMap<String, String>, client: Client, g_header: HashMap<String, String>) -> Result<Task> {
+    pub async fn make_task(&self,  cur_env: HashMap<String, String>, ctx: &TaskContext) -> Result<Task> {
         match self {
-            Job::HttpJob(job) => {job.make_task(cur_env, client, g_header).await},
+            Job::HttpJob(job) => {job.make_task(cur_env, ctx).await},
             Job::HtmlJob(job) => {job.make_task(cur_env).await},
             Job::CsvJob(job) => {job.make_task(cur_env).await},
             Job::ShellJob(job) => {job.make_task(cur_env).await},
@@ -39,6 +55,7 @@ impl Job {
 
 }
 
+#[allow(clippy::enum_variant_names, clippy::large_enum_variant)]
 #[derive(Clone, Debug)]
 pub enum Task {
     HttpTask(HttpTask),
@@ -56,4 +73,44 @@ impl Task {
             Task::ShellTask(task) => {task.run().await}
         }
     }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Task::HttpTask(task) => task.save_path.clone(),
+            Task::HtmlTask(task) => task.save_path.clone(),
+            Task::CsvTask(task) => task.save_path.clone(),
+            Task::ShellTask(task) => format!("{} {:?}", task.shell, task.args),
+        }
+    }
+
+    pub fn preview(&self) -> TaskPreview {
+        match self {
+            Task::HttpTask(task) => TaskPreview {
+                method: Some(task.method.to_string()),
+                url: Some(task.url.clone()),
+                header: Some(
+                    task.header
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+                        .collect(),
+                ),
+                body: task.body.as_ref().map(|body| body.describe()),
+                save_path: Some(task.save_path.clone()),
+                ..TaskPreview::default()
+            },
+            Task::HtmlTask(task) => TaskPreview {
+                save_path: Some(task.save_path.clone()),
+                ..TaskPreview::default()
+            },
+            Task::CsvTask(task) => TaskPreview {
+                save_path: Some(task.save_path.clone()),
+                ..TaskPreview::default()
+            },
+            Task::ShellTask(task) => TaskPreview {
+                command: Some(self.describe()),
+                save_path: Some(task.working_dir.clone()),
+                ..TaskPreview::default()
+            },
+        }
+    }
 }
\ No newline at end of file
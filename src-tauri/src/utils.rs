@@ -1,5 +1,4 @@
 use std::collections::HashMap;
-use std::io::Write;
 use chrono::{DateTime, Local, Utc};
 use handlebars::Handlebars;
 use sanitize_filename::sanitize;
@@ -9,21 +8,53 @@ pub fn get_json_val(json: &Value, path: &str) -> Option<String> {
     let Ok(values) = jsonpath_lib::select(json, path) else {
         return None;
     };
-    let Some(&val) = values.first() else {
-        return None;
-    };
+    let &val = values.first()?;
     match val {
         Value::String(s) => Some(s.clone().trim().to_string()),
         _ => Some(val.to_string().trim().to_string()),
     }
 }
 
+/// Whether a template uses `{{name}}` (or `{{{name}}}`), not just a longer name starting with it.
+pub fn uses_var(template: &str, name: &str) -> bool {
+    let pattern = format!(r"\{{\{{\{{?\s*{}\s*\}}?\}}\}}", regex::escape(name));
+    regex::Regex::new(&pattern).map(|re| re.is_match(template)).unwrap_or(false)
+}
+
+pub fn decode_bytes(bytes: &[u8], encoding: Option<&str>) -> crate::models::Result<String> {
+    let label = encoding.unwrap_or("utf-8");
+    let Some(enc) = encoding_rs::Encoding::for_label(label.as_bytes()) else {
+        return Err(crate::models::ApiError::ScrapingError(format!("unknown encoding {}", label)));
+    };
+    let (text, _, _) = enc.decode(bytes);
+    Ok(text.into_owned())
+}
+
 pub fn get_handlebars(s: &str, env: &HashMap<String, String>) -> crate::models::Result<String> {
     let mut handlebars = Handlebars::new();
     handlebars.register_template_string("output", s)?;
     Ok(handlebars.render("output", &env)?)
 }
 
+/// Like `get_handlebars` but without HTML escaping, for request bodies.
+pub fn get_handlebars_raw(s: &str, env: &HashMap<String, String>) -> crate::models::Result<String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.register_template_string("output", s)?;
+    Ok(handlebars.render("output", &env)?)
+}
+
+/// Escapes values for use inside JSON strings.
+pub fn get_handlebars_json(s: &str, env: &HashMap<String, String>) -> crate::models::Result<String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(|v| {
+        let quoted = serde_json::to_string(v).unwrap_or_default();
+        quoted[1..quoted.len() - 1].to_string()
+    });
+    handlebars.register_template_string("output", s)?;
+    Ok(handlebars.render("output", &env)?)
+}
+
 pub fn get_handlebars_safe_dir(s: &str, env: &HashMap<String, String>) -> crate::models::Result<String> {
     let mut new_env = env.clone();
     for (_k, v) in new_env.iter_mut() {
@@ -45,8 +76,22 @@ pub fn from_unix_time(s: String) -> crate::models::Result<String> {
     Ok(datetime_local.format("%Y-%m-%d %H:%M:%S").to_string())
 }
 
+#[cfg(feature = "gui")]
 pub async fn save_file(file_path: String, txt: String) -> crate::models::Result<()> {
+    use std::io::Write;
     let mut file = std::fs::File::create(file_path)?;
     file.write_all(txt.as_bytes())?;
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uses_var_matches_whole_placeholder() {
+        assert!(uses_var("list_{{PAGE}}.json", "PAGE"));
+        assert!(uses_var("?p={{{ PAGE }}}", "PAGE"));
+        assert!(!uses_var("list_{{PAGE_SIZE}}.json", "PAGE"));
+        assert!(!uses_var("list_PAGE.json", "PAGE"));
+    }
+}
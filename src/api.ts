@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import { Setting, TextContent } from "@/bindings.ts";
+import { Setting, StepPreview, StepStats, TextContent } from "@/bindings.ts";
 
 export const getArgPath = async (): Promise<string | undefined> => {
   return await invoke("get_arg_path")
@@ -17,6 +17,24 @@ export const runStep = async (stepName: string): Promise<void> => {
   return await invoke("run_step", {stepName})
 }
 
+export const runPipeline = async (): Promise<void> => {
+  console.log('invoke run_pipeline:')
+  return await invoke("run_pipeline")
+}
+
+export const stopPipeline = async (): Promise<void> => {
+  console.log('invoke stop_pipeline:')
+  return await invoke("stop_pipeline")
+}
+
+export const previewStep = async (stepName: string, limit: number): Promise<StepPreview> => {
+  return await invoke("preview_step", {stepName, limit})
+}
+
+export const getStepStats = async (stepName: string): Promise<StepStats> => {
+  return await invoke("get_step_stats", {stepName})
+}
+
 
 export const saveSetting = async (filePath: string, txt: string): Promise<void> => {
   console.log('invoke save_setting:', filePath, txt)
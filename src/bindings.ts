@@ -40,6 +40,38 @@ async runStep(stepName: string) : Promise<Result<null, ApiError>> {
     else return { status: "error", error: e  as any };
 }
 },
+async runPipeline() : Promise<Result<null, ApiError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("run_pipeline") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stopPipeline() : Promise<Result<null, ApiError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("stop_pipeline") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async previewStep(stepName: string, limit: number) : Promise<Result<StepPreview, ApiError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_step", { stepName, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getStepStats(stepName: string) : Promise<Result<StepStats, ApiError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_step_stats", { stepName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async saveSetting(filePath: string, txt: string) : Promise<Result<null, ApiError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("save_setting", { filePath, txt }) };
@@ -68,21 +100,273 @@ async updateState(stepName: string, val: number) : Promise<Result<null, ApiError
 
 /** user-defined types **/
 
-export type ApiError = { ScrapingError: string } | { TemplateError: string } | { ReqwestError: string } | { Io: string } | { ParseError: string } | { JsonError: string } | { GlobError: string }
+export type ApiError = { ScrapingError: string } | { TemplateError: string } | { ReqwestError: string } | { Timeout: string } | { Connect: string } | { HttpStatus: [number, number | null, string] } | { Io: string } | { ParseError: string } | { JsonError: string } | { GlobError: string } | { IterError: string } | { AuthError: string }
+export type AuthConfig = { method: AuthMethod; 
+/**
+ * env name the token is stored under, "TOKEN" when omitted
+ */
+token_name: string | null; 
+/**
+ * headers added to every HttpJob request, templated over the env with the token;
+ * `Authorization: Basic/Bearer {{TOKEN}}` when omitted, `{}` to use the token only in your own templates
+ */
+header: Partial<{ [key in string]: string }> | null }
+export type AuthMethod = { Basic: { username: string; password: string } } | { Bearer: { token: string } } | { OAuth2: OAuth2Auth } | { Login: LoginAuth }
+export type CacheConfig = { 
+/**
+ * `<output>/.http_cache` when omitted
+ */
+dir: string | null; 
+/**
+ * request headers that are part of the cache key besides method, url and body
+ */
+vary: string[] | null; 
+/**
+ * freshness when the response has no Cache-Control max-age or Expires
+ */
+max_age_s: number | null }
+export type CookieConfig = { 
+/**
+ * Netscape cookies.txt, loaded on setting load and rewritten after each step.
+ */
+file: string | null; 
+/**
+ * Url to `name=value; name2=value2` cookies set before the first request.
+ */
+seed: Partial<{ [key in string]: string }> | null }
 export type CsvJob = { keys: string[]; sep: string; filename: string; output: string }
+export type DateUnit = "Hours" | "Days" | "Months"
+/**
+ * Where a value is taken from in the response.
+ */
+export type Extractor = { JsonPath: string } | { Header: string } | 
+/**
+ * first capture group, or the whole match when the regex has no groups
+ */
+{ Regex: string }
 export type HtmlJob = { json_map: Partial<{ [key in string]: ([string, string])[] }>; output_template_file: string; output_template: string | null; filename: string; output: string }
-export type HttpJob = { url: string; method: string; header: Partial<{ [key in string]: string }>; filename: string; output: string }
+/**
+ * Request body; every string is a template over the task env.
+ */
+export type HttpBody = { Raw: { text: string; content_type: string | null } } | 
+/**
+ * JSON text; values are JSON-escaped, so `"{{Q}}"` is safe for any Q and `{{PAGE}}` stays a number
+ */
+{ Json: string } | { Form: Partial<{ [key in string]: string }> } | { Multipart: MultipartPart[] }
+export type HttpJob = { url: string; method: string; header: Partial<{ [key in string]: string }>; filename: string; output: string; body: HttpBody | null; pagination: Pagination | null; 
+/**
+ * values taken from the response and added to the env of `then`
+ */
+extract: Partial<{ [key in string]: Extractor }> | null; 
+/**
+ * requests made after this one in the same task, e.g. a detail page then its attachment
+ */
+then: HttpJob[] | null; 
+/**
+ * what to do when the file is already saved, `Never` when omitted
+ */
+refresh: RefreshMode | null; cache: CacheConfig | null; 
+/**
+ * send a `method` outside GET/POST/PUT/PATCH/DELETE/HEAD/OPTIONS/CONNECT/TRACE as is
+ */
+allow_custom_method?: boolean }
+export type IterCsv = { path: string; delimiter: string | null; encoding: string | null; has_header: boolean | null; 
+/**
+ * column names when the file has no header row
+ */
+columns: string[] | null; 
+/**
+ * env name -> column name; all columns by their own name when omitted
+ */
+env_map: Partial<{ [key in string]: string }> | null }
+export type IterDateRange = { name: string; 
+/**
+ * `2025-01-01`, `2025-01-01 09:00:00`, `today` or `now`; templated from env
+ */
+start: string; end: string; step: number | null; unit: DateUnit | null; 
+/**
+ * chrono format string, `%Y-%m-%d` when omitted
+ */
+format: string | null; 
+/**
+ * IANA name such as `Asia/Seoul`; local time when omitted
+ */
+timezone: string | null; 
+/**
+ * include `end` itself; false when omitted, like the other ranges
+ */
+inclusive: boolean | null }
+export type IterErrorPolicy = "Skip" | "Abort"
+/**
+ * Yields `{name}_PATH`, `_NAME`, `_STEM`, `_EXT`, `_DIR`, `_SIZE`, `_MTIME` and `_MIME` per file.
+ */
+export type IterFiles = { name: string; dir: string; recursive: boolean | null; max_depth: number | null; 
+/**
+ * glob patterns matched against the path relative to `dir`, e.g. `**/*.pdf`
+ */
+include: string[] | null; exclude: string[] | null }
+/**
+ * Wraps another iterator; `filter`, then `distinct_by`, then `skip`/`limit` are applied in that order.
+ */
+export type IterFilter = { iter: TaskIter; filter: IterPredicate | null; 
+/**
+ * template over the item; items rendering an already seen value are dropped for the rest of the step run
+ */
+distinct_by: string | null; skip: number | null; limit: number | null }
+export type IterGlobHtmlPattern = { glob_pattern: string; 
+/**
+ * CSS selector; one item per matching node
+ */
+item_selector: string; 
+/**
+ * env name -> `selector` (text), `selector@attr`, `@attr` of the item, or "" for the item text
+ */
+env_pattern: Partial<{ [key in string]: string }>; encoding: string | null }
 export type IterGlobJsonPattern = { glob_pattern: string; item_pattern: string; env_pattern: Partial<{ [key in string]: string }> }
-export type IterJsonRangePattern = { name: string; file_pattern: string; offset_pattern: string; take_pattern: string }
+export type IterGlobXmlPattern = { glob_pattern: string; 
+/**
+ * element path from the root such as `urlset/url` or `rss/channel/item`; `//item` matches at any depth
+ */
+item_path: string; 
+/**
+ * env name -> path inside the item (`loc`, `enclosure/@url`, `@id`); namespace prefixes are ignored
+ */
+env_pattern: Partial<{ [key in string]: string }>; 
+/**
+ * load the child sitemaps of a `sitemapindex`, true when omitted
+ */
+follow_sitemap_index: boolean | null }
+export type IterJsonLines = { path: string; encoding: string | null; 
+/**
+ * env name -> JSONPath; top-level fields by their own name when omitted
+ */
+env_pattern: Partial<{ [key in string]: string }> | null }
+export type IterJsonRangePattern = { name: string; file_pattern: string; offset_pattern: string; take_pattern: string | null; end_pattern: string | null; step_pattern: string | null; inclusive: boolean | null }
 export type IterList = { name: string; val: string[] }
+/**
+ * How a step combines its `task_iters`.
+ */
+export type IterMode = 
+/**
+ * Nested loops; later iterators see the values of earlier ones.
+ */
+"Product" | 
+/**
+ * The i-th items of all iterators together; stops at the shortest.
+ */
+"Zip" | 
+/**
+ * Like `Zip`, but runs until the longest ends, leaving finished iterators' values unset.
+ */
+"ZipLongest" | 
+/**
+ * Every item of the first iterator, then every item of the next, and so on.
+ */
+"Chain"
 export type IterPattern = { name: string; glob_pattern: string; content_pattern: string }
-export type IterRange = { name: string; offset: string; take: string }
-export type IterRangePattern = { name: string; glob_pattern: string; offset: string; take: string }
+export type IterPredicate = 
+/**
+ * kept when the rendered text is not empty, "false" or "0"
+ */
+{ Template: string } | 
+/**
+ * kept when the JSONPath matches `[item]`, e.g. `$[?(@.TYPE == 'B')]`
+ */
+{ JsonPath: string }
+export type IterRange = { name: string; offset: string; 
+/**
+ * number of values; ignored when `end` is set
+ */
+take: string | null; 
+/**
+ * exclusive unless `inclusive`
+ */
+end: string | null; 
+/**
+ * may be negative; 1 when omitted, or -1 when `end` is below `offset`
+ */
+step: string | null; inclusive: boolean | null }
+export type IterRangePattern = { name: string; glob_pattern: string; offset: string; take: string | null; end: string | null; step: string | null; inclusive: boolean | null }
 export type Job = { HttpJob: HttpJob } | { HtmlJob: HtmlJob } | { ShellJob: ShellJob } | { CsvJob: CsvJob }
-export type Setting = { env: Partial<{ [key in string]: string }>; header: Partial<{ [key in string]: string }>; steps: Partial<{ [key in string]: Step }> }
+/**
+ * Custom login request; cookies it sets land in the cookie jar.
+ */
+export type LoginAuth = { url: string; method: string; header?: Partial<{ [key in string]: string }>; body: HttpBody | null; 
+/**
+ * JSONPath to the token; without it the login only sets cookies
+ */
+token_path: string | null }
+export type MultipartPart = { name: string; value: string | null; 
+/**
+ * path of a file to upload instead of `value`
+ */
+file: string | null; filename: string | null; content_type: string | null }
+/**
+ * Client-credentials grant, or refresh-token grant when `refresh_token` is set or returned.
+ */
+export type OAuth2Auth = { token_url: string; client_id: string; client_secret: string | null; scope: string | null; refresh_token: string | null; 
+/**
+ * JSONPath to the token, "$.access_token" when omitted
+ */
+token_path: string | null; 
+/**
+ * JSONPath to a new refresh token, "$.refresh_token" when omitted
+ */
+refresh_token_path: string | null }
+/**
+ * Fetches page after page, feeding `cursor_name`/`page_name` into the url, header and filename templates.
+ */
+export type Pagination = { cursor_name: string; page_name: string | null; 
+/**
+ * first cursor, "" when omitted
+ */
+start: string | null; 
+/**
+ * JSONPath to the next cursor; stops when missing, empty or unchanged
+ */
+next_cursor: string | null; 
+/**
+ * JSONPath to a has-more flag; stops when false, 0 or missing
+ */
+has_more: string | null; 
+/**
+ * JSONPath to the page items; stops when it selects nothing
+ */
+items: string | null; 
+/**
+ * at least 1; no limit when omitted
+ */
+max_pages: number | null }
+export type RateLimit = { requests_per_second: number | null; requests_per_minute: number | null; min_delay_ms: number | null; jitter_ms: number | null }
+/**
+ * When a file that is already saved is requested again.
+ */
+export type RefreshMode = 
+/**
+ * keep the saved file
+ */
+"Never" | 
+/**
+ * request again once the response's max-age/Expires (or `max_age_s`) has passed
+ */
+"IfStale" | 
+/**
+ * request again on every run
+ */
+"Always"
+export type RetryErrorKind = "Timeout" | "Connect" | "Reqwest" | "Io"
+export type RetryPolicy = { max_attempts: number; base_delay_ms: number; max_delay_ms: number | null; jitter_ms: number | null; retry_statuses?: number[]; retry_errors?: RetryErrorKind[] }
+export type Setting = { env: Partial<{ [key in string]: string }>; header: Partial<{ [key in string]: string }>; steps: Partial<{ [key in string]: Step }>; edges?: ([string, string])[]; rate_limit: RateLimit | null; cookies: CookieConfig | null; auth: AuthConfig | null }
 export type ShellJob = { shell: string; args: string[]; working_dir: string; encoding: string }
-export type Step = { name: string; task_iters: TaskIter[]; job: Job; concurrency_limit: number }
-export type TaskIter = { Range: IterRange } | { Pattern: IterPattern } | { RangePattern: IterRangePattern } | { Vec: IterList } | { GlobJsonPattern: IterGlobJsonPattern } | { GlobJsonRangePattern: IterJsonRangePattern }
+export type Step = { name: string; task_iters: TaskIter[]; iter_mode?: IterMode; job: Job; concurrency_limit: number; ledger: string | null; retry: RetryPolicy | null; rate_limit: RateLimit | null; on_iter_error?: IterErrorPolicy; precount?: boolean }
+export type StepPreview = { total: number; tasks: TaskPreview[]; errors: string[] }
+export type StepStats = { step: string; total: number | null; 
+/**
+ * `total` minus the tasks processed or running; None without a total
+ */
+queued: number | null; running: number; succeeded: number; skipped: number; failed: number; elapsed_ms: number; throughput: number; eta_ms: number | null }
+export type TaskIter = { Range: IterRange } | { Pattern: IterPattern } | { RangePattern: IterRangePattern } | { Vec: IterList } | { GlobJsonPattern: IterGlobJsonPattern } | { GlobJsonRangePattern: IterJsonRangePattern } | { Csv: IterCsv } | { JsonLines: IterJsonLines } | { Filter: IterFilter } | { DateRange: IterDateRange } | { GlobHtmlPattern: IterGlobHtmlPattern } | { GlobXmlPattern: IterGlobXmlPattern } | { Files: IterFiles }
+export type TaskPreview = { vals: Partial<{ [key in string]: string }>; method?: string | null; url?: string | null; header?: Partial<{ [key in string]: string }> | null; body?: string | null; save_path?: string | null; command?: string | null }
 export type TextContent = { path: string; mimetype: string; enc?: string | null; text?: string | null }
 
 /** tauri-specta globals **/
@@ -1,4 +1,12 @@
 // This file has been generated by Specta. DO NOT EDIT.
 
+export type StepEvent = { StepWaiting: { step: string; parents: string[] } } | { StepStarted: { step: string } } | { IterError: { step: string; error: string } } | { TaskFinished: { step: string; message: string } } | { TaskSkipped: { step: string; message: string } } | { TaskRetry: { step: string; message: string; attempt: number; delay_ms: number; error: string } } | { TaskFailed: { step: string; message: string; error: string } } | { StepEnded: { step: string } } | { StepStopped: { step: string } } | { StepSkipped: { step: string } } | { StepFailed: { step: string; error: string } } | { Progress: { stats: StepStats } }
+
 export type StepNotify = { name: string; status: string; message: string }
 
+export type StepStats = { step: string; total: number | null; 
+/**
+ * `total` minus the tasks processed or running; None without a total
+ */
+queued: number | null; running: number; succeeded: number; skipped: number; failed: number; elapsed_ms: number; throughput: number; eta_ms: number | null }
+